@@ -0,0 +1,138 @@
+-- Schema used by the daemon, tables already existing on PokéSpawn installations are left untouched
+
+CREATE TABLE IF NOT EXISTS city (
+    id SMALLINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    name VARCHAR(255) NOT NULL,
+    coordinates TEXT NOT NULL,
+    scadenza BIGINT NOT NULL DEFAULT 0,
+    monitor TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    admins_users VARCHAR(255) NOT NULL DEFAULT '',
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS city_parks (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    city_id SMALLINT UNSIGNED NOT NULL,
+    coordinates TEXT NOT NULL,
+    PRIMARY KEY (id),
+    KEY city_id (city_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS city_park_stats (
+    park_id BIGINT UNSIGNED NOT NULL,
+    encounter_id VARCHAR(32) NOT NULL,
+    pokemon_id SMALLINT UNSIGNED NOT NULL,
+    PRIMARY KEY (park_id, encounter_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS utenti (
+    user_id BIGINT UNSIGNED NOT NULL,
+    city_id SMALLINT UNSIGNED NOT NULL,
+    status TINYINT UNSIGNED NOT NULL DEFAULT 1,
+    PRIMARY KEY (user_id),
+    KEY city_id (city_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS utenti_config_bot (
+    user_id BIGINT UNSIGNED NOT NULL,
+    enabled TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    beta TINYINT UNSIGNED NOT NULL DEFAULT 1,
+    config TEXT NOT NULL,
+    sent INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS utenti_bot_stats (
+    user_id BIGINT UNSIGNED NOT NULL,
+    day DATE NOT NULL,
+    sent INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, day)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS utenti_temp_pos (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    user_id BIGINT UNSIGNED NOT NULL,
+    pos_type CHAR(1) NOT NULL,
+    latitude DOUBLE NOT NULL,
+    longitude DOUBLE NOT NULL,
+    city_id SMALLINT UNSIGNED NULL,
+    start_time BIGINT NOT NULL,
+    end_time BIGINT NOT NULL,
+    PRIMARY KEY (id),
+    KEY user_id (user_id, start_time, end_time)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS bot_stats (
+    day DATE NOT NULL,
+    events BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (day)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS bot_sent_pkmn (
+    pokemon_id SMALLINT UNSIGNED NOT NULL,
+    sent INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (pokemon_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS bot_sent_raid (
+    raid_id VARCHAR(8) NOT NULL,
+    sent INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (raid_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS bot_weather_watches (
+    user_id BIGINT UNSIGNED NOT NULL,
+    encounter_id VARCHAR(32) NOT NULL,
+    pokemon_id SMALLINT UNSIGNED NOT NULL,
+    iv TINYINT UNSIGNED NULL,
+    latitude DOUBLE NOT NULL,
+    longitude DOUBLE NOT NULL,
+    expire BIGINT NOT NULL,
+    KEY user_id (user_id, encounter_id),
+    KEY expire (expire)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS device_tier (
+    id BIGINT UNSIGNED NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    url VARCHAR(255) NOT NULL,
+    release_date DATE NOT NULL,
+    app_version VARCHAR(32) NOT NULL,
+    api_version VARCHAR(32) NOT NULL,
+    reboot TINYINT(1) NOT NULL DEFAULT 0,
+    uninstall TINYINT(1) NOT NULL DEFAULT 0,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS pokemon_list (
+    id SMALLINT UNSIGNED NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    type VARCHAR(64) NULL,
+    rarity TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    scanned TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    status TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    raid TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS pokemon_moves (
+    id SMALLINT UNSIGNED NOT NULL,
+    move VARCHAR(64) NOT NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS pokemon_forms (
+    id SMALLINT UNSIGNED NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    pokemon_id SMALLINT UNSIGNED NULL,
+    hidden TINYINT(1) NOT NULL DEFAULT 0,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS grunt_types (
+    id SMALLINT UNSIGNED NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    sex VARCHAR(16) NULL,
+    type VARCHAR(32) NULL,
+    PRIMARY KEY (id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
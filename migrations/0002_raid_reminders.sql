@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS bot_raid_reminders (
+    user_id BIGINT UNSIGNED NOT NULL,
+    gym_id VARCHAR(64) NOT NULL,
+    raid_start BIGINT NOT NULL,
+    kind TINYINT UNSIGNED NOT NULL,
+    due BIGINT NOT NULL,
+    raid_end BIGINT NOT NULL,
+    raid TEXT NOT NULL,
+    PRIMARY KEY (user_id, gym_id, raid_start, kind),
+    KEY raid_end (raid_end)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS bot_sent_messages (
+    user_id BIGINT UNSIGNED NOT NULL,
+    message_id BIGINT NOT NULL,
+    expire BIGINT NOT NULL,
+    PRIMARY KEY (user_id, message_id),
+    KEY expire (expire)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
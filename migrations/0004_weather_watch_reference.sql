@@ -0,0 +1 @@
+ALTER TABLE bot_weather_watches ADD COLUMN weather TINYINT UNSIGNED NULL AFTER expire;
@@ -0,0 +1,2 @@
+-- base height and weight from the game master, needed to classify Pokémon sizes
+ALTER TABLE pokemon_list ADD COLUMN height DOUBLE NULL AFTER type, ADD COLUMN weight DOUBLE NULL AFTER height;
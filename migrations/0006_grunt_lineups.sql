@@ -0,0 +1,3 @@
+-- expected lineup, battle slots separated by ";" and Pokémon ids by "," (e.g. "1,4;2,5;3,6")
+-- rewards lists the battle slots whose Pokémon can be caught at the end (e.g. "1,2")
+ALTER TABLE grunt_types ADD COLUMN lineup VARCHAR(255) NULL, ADD COLUMN rewards VARCHAR(16) NULL;
@@ -0,0 +1,27 @@
+-- sizes and lineups overrides, moved out of the PokéSpawn tables altered by migrations 5 and 6
+CREATE TABLE IF NOT EXISTS bot_pokemon_sizes (
+    pokemon_id SMALLINT UNSIGNED NOT NULL,
+    -- base height and weight, NULL to use the ones from the game master
+    height DOUBLE NULL,
+    weight DOUBLE NULL,
+    PRIMARY KEY (pokemon_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS bot_grunt_lineups (
+    grunt_type SMALLINT UNSIGNED NOT NULL,
+    -- expected lineup, battle slots separated by ";" and Pokémon ids by "," (e.g. "1,4;2,5;3,6")
+    lineup VARCHAR(255) NOT NULL,
+    -- battle slots whose Pokémon can be caught at the end (e.g. "1,2")
+    rewards VARCHAR(16) NOT NULL DEFAULT '',
+    PRIMARY KEY (grunt_type)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+INSERT IGNORE INTO bot_pokemon_sizes (pokemon_id, height, weight)
+    SELECT id, height, weight FROM pokemon_list WHERE height IS NOT NULL OR weight IS NOT NULL;
+
+INSERT IGNORE INTO bot_grunt_lineups (grunt_type, lineup, rewards)
+    SELECT id, lineup, IFNULL(rewards, '') FROM grunt_types WHERE lineup IS NOT NULL;
+
+ALTER TABLE pokemon_list DROP COLUMN height, DROP COLUMN weight;
+
+ALTER TABLE grunt_types DROP COLUMN lineup, DROP COLUMN rewards;
@@ -0,0 +1,8 @@
+-- egg notifications to be edited when the boss is revealed, expire is NULL if the user doesn't delete old messages
+ALTER TABLE bot_sent_messages
+    MODIFY expire BIGINT NULL,
+    ADD COLUMN gym_id VARCHAR(64) NULL,
+    ADD COLUMN raid_start BIGINT NULL,
+    ADD COLUMN raid_end BIGINT NULL,
+    ADD KEY egg (user_id, gym_id, raid_start),
+    ADD KEY raid_end (raid_end);
@@ -14,52 +14,57 @@ const INTERVAL: i64 = 900;
 
 pub fn init() {
     spawn(async {
-        if let (Some(bot_token), Some(chat_id)) =
-            (CONFIG.telegram.alert_bot_token.as_ref(), CONFIG.telegram.alert_chat.as_ref())
-        {
-            let mut interval = interval(Duration::from_secs(INTERVAL as u64));
-            loop {
-                interval.tick().await;
-
-                let now = Utc::now();
-                let timestamp = now.timestamp();
-
-                let mut alerts = Vec::new();
-                let citystats = CITYSTATS.read().await;
-                for (city_id, stats) in citystats.iter() {
-                    let cities = CITIES.load();
-                    let city = match cities.get(city_id) {
-                        Some(c) => c,
-                        None => continue,
-                    };
-
-                    // if the quest scanner is active, other scans are obviously unactive
-                    if city.scadenza < timestamp || stats.last_quest > Some(timestamp - 60) {
-                        continue;
-                    }
-
-                    let mut city_alerts = Vec::new();
-                    check_timestamp(&stats.last_pokemon, timestamp, "Pokémon", &mut city_alerts);
-                    if city.scan_iv > 0 {
-                        check_timestamp(&stats.last_iv, timestamp, "IV", &mut city_alerts);
-                    }
-                    // if now.hour() >= 6 && now.hour() <= 20 {
-                    //     check_timestamp(&stats.last_raid, timestamp, "Raid", &mut city_alerts);
-                    //     check_timestamp(&stats.last_invasion, timestamp, "Invasioni", &mut city_alerts);
-                    // }
-                    check_timestamp(&stats.last_quest, timestamp - 86400, "Quest", &mut city_alerts);
-
-                    if !city_alerts.is_empty() {
-                        alerts.push(format!(
-                            "@{} la zona {} non ha scansioni:\n{}",
-                            city.admins_users.join(", @"),
-                            city.name,
-                            city_alerts.join("\n")
-                        ));
-                    }
+        let mut interval = interval(Duration::from_secs(INTERVAL as u64));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = crate::shutdown::wait() => break,
+            }
+
+            let now = Utc::now();
+            let timestamp = now.timestamp();
+
+            let mut alerts = Vec::new();
+            let citystats = CITYSTATS.read().await;
+            for (city_id, stats) in citystats.iter() {
+                let cities = CITIES.load();
+                let city = match cities.get(city_id) {
+                    Some(c) => c,
+                    None => continue,
+                };
+
+                // if the quest scanner is active, other scans are obviously unactive
+                if city.scadenza < timestamp || stats.last_quest > Some(timestamp - 60) {
+                    continue;
+                }
+
+                let mut city_alerts = Vec::new();
+                check_timestamp(&stats.last_pokemon, timestamp, "Pokémon", &mut city_alerts);
+                if city.scan_iv > 0 {
+                    check_timestamp(&stats.last_iv, timestamp, "IV", &mut city_alerts);
+                }
+                // if now.hour() >= 6 && now.hour() <= 20 {
+                //     check_timestamp(&stats.last_raid, timestamp, "Raid", &mut city_alerts);
+                //     check_timestamp(&stats.last_invasion, timestamp, "Invasioni", &mut city_alerts);
+                // }
+                check_timestamp(&stats.last_quest, timestamp - 86400, "Quest", &mut city_alerts);
+
+                if !city_alerts.is_empty() {
+                    alerts.push(format!(
+                        "@{} la zona {} non ha scansioni:\n{}",
+                        city.admins_users.join(", @"),
+                        city.name,
+                        city_alerts.join("\n")
+                    ));
                 }
+            }
 
-                if !alerts.is_empty() {
+            if !alerts.is_empty() {
+                // tokens are read every time, so they can be changed without restarting
+                let config = CONFIG.load_full();
+                if let (Some(bot_token), Some(chat_id)) =
+                    (config.telegram.alert_bot_token.as_ref(), config.telegram.alert_chat.as_ref())
+                {
                     send_message(bot_token, chat_id, &alerts.join("\n\n")).send().await.ok();
                 }
             }
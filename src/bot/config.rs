@@ -4,7 +4,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use serde_json::Value as JsonValue;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
 
 use chrono_tz::Europe::Rome;
 
@@ -21,17 +21,44 @@ use rocketmap_entities::{gamemaster, Gender, GymDetails, Pokemon, Pokestop, PvpR
 use crate::Platform;
 // use crate::lists::COMMON;
 use crate::db::MYSQL;
-use crate::lists::{CITIES, FORMS, LIST};
+use crate::lists::{self, CITIES, FORMS, GRUNTS, LIST, MOVES};
 // use crate::telegram::Image;
 
 use super::{
+    gyms::{self, GymEvent},
+    map::Overlay,
     message::{GymMessage, InvasionMessage, LureMessage, Message, PokemonMessage, RaidMessage, WeatherMessage},
-    WATCHES,
+    pvp, WATCHES,
 };
 
 const MAX_DISTANCE: f64 = 15f64;
 // const MIN_IV_LIMIT: f32 = 36f32;
 
+/// Where a matched notification goes, according to the time configs
+pub enum Delivery<M> {
+    /// sent right away
+    Now(M),
+    /// out of hours in quiet mode, kept for the summary sent when the next active window starts
+    Away(M),
+}
+
+impl<M: Message + Send + Sync + 'static> Delivery<M> {
+    fn new(message: M, now: bool) -> Self {
+        if now {
+            Delivery::Now(message)
+        } else {
+            Delivery::Away(message)
+        }
+    }
+
+    fn boxed(self) -> Delivery<Box<dyn Message + Send + Sync>> {
+        match self {
+            Delivery::Now(message) => Delivery::Now(Box::new(message)),
+            Delivery::Away(message) => Delivery::Away(Box::new(message)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct BotConfig {
@@ -45,6 +72,8 @@ pub struct BotConfig {
     pub qest: Option<BotQest>,
     pub lure: Option<BotPkst>,
     pub invs: Option<BotPkst>,
+    pub gym: Option<BotGym>,
+    pub favs: Option<BotFavs>,
     pub more: BotMore,
 }
 
@@ -197,42 +226,94 @@ impl BotConfig {
         Ok(true)
     }
 
+    fn is_favorite_gym(&self, gym_id: &str) -> bool {
+        self.favs.as_ref().map(|f| f.g.iter().any(|id| id == gym_id)) == Some(true)
+    }
+
+    fn is_favorite_pokestop(&self, pokestop_id: &str) -> bool {
+        self.favs.as_ref().map(|f| f.s.iter().any(|id| id == pokestop_id)) == Some(true)
+    }
+
+    /// Position used for filtering and its radius, when the user wants them on maps
+    fn get_overlay(&self, loc: &[JsonValue], rad: f64) -> Option<Overlay> {
+        if self.more.m != Some(1) {
+            return None;
+        }
+        Some(Overlay {
+            lat: BotLocs::convert_to_f64(loc.first()?).ok()?,
+            lon: BotLocs::convert_to_f64(loc.get(1)?).ok()?,
+            radius: rad,
+        })
+    }
+
     pub async fn submit<PC, FC>(
         &self,
         now: &DateTime<Utc>,
         platform: &Platform,
         input: &Request<PC, FC>,
-    ) -> Result<Box<dyn Message + Send + Sync>, ()>
+    ) -> Result<Delivery<Box<dyn Message + Send + Sync>>, ()>
     where
         PC: gamemaster::Cache<Id = u16>,
         FC: gamemaster::Cache<Id = u16>,
     {
-        if !self.time.is_active(now)? && self.time.fi[0] == 0 && self.time.fl[0] == 0 {
+        // snooze silences everything, bypasses and quiet hours summary included
+        if self.time.is_snoozed(now) {
+            #[cfg(test)]
+            info!("Webhook discarded for snooze");
+
+            return Err(());
+        }
+
+        let active = self.time.is_active(now)?;
+        // out of hours in quiet mode, matching notifications are collected for the summary
+        let quiet = !active && self.time.q == Some(1);
+        // out of hours notifications are sent right away only with a time bypass
+        let allowed = active || self.time.has_bypass();
+        if !allowed && !quiet {
             #[cfg(test)]
             info!("Webhook discarded for time configs");
 
             Err(())
         } else {
             match input {
-                Request::Pokemon(i) => Ok(Box::new(self.submit_pokemon(now, platform, i)?)),
-                Request::Raid(i) => Ok(Box::new(self.submit_raid(now, platform, i)?)),
+                Request::Pokemon(i) => self.submit_pokemon(now, platform, i, active, quiet).map(Delivery::boxed),
+                Request::Raid(i) => self.submit_raid(now, platform, i, active, quiet).map(Delivery::boxed),
                 Request::Pokestop(i) => {
                     // MAD workaround
                     if i.lure_id.is_none() && i.grunt_type.is_some() {
-                        Ok(Box::new(self.submit_invasion(now, platform, i)?))
+                        Ok(Delivery::new(self.submit_invasion(now, platform, i)?, allowed).boxed())
                     } else {
-                        Ok(Box::new(self.submit_pokestop(now, platform, i)?))
+                        Ok(Delivery::new(self.submit_pokestop(now, platform, i)?, allowed).boxed())
                     }
                 }
-                Request::Invasion(i) => Ok(Box::new(self.submit_invasion(now, platform, i)?)),
-                Request::GymDetails(i) => Ok(Box::new(self.submit_gym(now, platform, i)?)),
-                Request::Weather(i) => Ok(Box::new(self.submit_weather(now, platform, i).await?)),
+                Request::Invasion(i) => Ok(Delivery::new(self.submit_invasion(now, platform, i)?, allowed).boxed()),
+                Request::GymDetails(i) => Ok(Delivery::Now(Box::new(self.submit_gym(now, platform, i, active).await?))),
+                Request::Weather(i) if allowed => {
+                    Ok(Delivery::Now(Box::new(self.submit_weather(now, platform, i).await?)))
+                }
                 _ => Err(()),
             }
         }
     }
 
-    fn submit_pokemon(&self, now: &DateTime<Utc>, platform: &Platform, input: &Pokemon) -> Result<PokemonMessage, ()> {
+    /// Out of hours and in quiet mode, so collected notifications have to wait
+    pub fn is_quiet(&self, now: &DateTime<Utc>) -> bool {
+        self.time.q == Some(1) && self.time.is_active(now) == Ok(false)
+    }
+
+    /// In active hours and not snoozed, as required by follow-ups like raid reminders
+    pub fn is_awake(&self, now: &DateTime<Utc>) -> bool {
+        self.time.is_active(now) == Ok(true)
+    }
+
+    fn submit_pokemon(
+        &self,
+        now: &DateTime<Utc>,
+        platform: &Platform,
+        input: &Pokemon,
+        active: bool,
+        quiet: bool,
+    ) -> Result<Delivery<PokemonMessage>, ()> {
         let loc = self.locs.get_pokemon_settings();
         let pos = (input.latitude, input.longitude);
         let iv = match (input.individual_attack, input.individual_defense, input.individual_stamina) {
@@ -253,22 +334,45 @@ impl BotConfig {
             if dist <= rad {
                 write!(debug, "Bypass IV {:.0}%", iv.unwrap_or_default()).map_err(|_| ())?;
 
-                return Ok(PokemonMessage {
-                    pokemon: input.clone(),
-                    iv,
-                    distance: BotLocs::calc_dist(&self.locs.h, pos)?,
-                    direction: BotLocs::get_direction(&self.locs.h, pos)?,
-                    debug: if self.debug == Some(true) { Some(debug) } else { None },
-                });
+                return Ok(Delivery::new(
+                    PokemonMessage {
+                        pokemon: input.clone(),
+                        iv,
+                        distance: BotLocs::calc_dist(&self.locs.h, pos)?,
+                        direction: BotLocs::get_direction(&self.locs.h, pos)?,
+                        debug: if self.debug == Some(true) { Some(debug) } else { None },
+                        overlay: self.get_overlay(loc, rad),
+                    },
+                    active || self.time.has_bypass(),
+                ));
             }
         }
 
         let pokemon_id = input.pokemon_id.to_string();
-        #[allow(clippy::unnecessary_lazy_evaluations)]
-        let filter = self.pkmn.l.get(&pokemon_id).ok_or_else(|| {
-            #[cfg(test)]
-            info!("Pokémon not configured");
-        })?;
+        if let Some(disguise) = get_disguise(input) {
+            let list = LIST.load();
+            let name = |id: u16| list.get(&id).map(|p| p.name.clone()).unwrap_or_else(|| format!("#{}", id));
+            writeln!(
+                debug,
+                "Pokémon travestito da {}, filtri della specie reale {}",
+                name(disguise),
+                name(input.pokemon_id)
+            )
+            .map_err(|_| ())?;
+        }
+        // explicit configurations override rules
+        let filter = match self.pkmn.l.get(&pokemon_id) {
+            Some(filter) => filter,
+            None => {
+                #[allow(clippy::unnecessary_lazy_evaluations)]
+                let (index, rule) = self.pkmn.get_rule(input.pokemon_id).ok_or_else(|| {
+                    #[cfg(test)]
+                    info!("Pokémon not configured");
+                })?;
+                writeln!(debug, "Regola generica n. {}", index + 1).map_err(|_| ())?;
+                &rule.f
+            }
+        };
         if filter.first() == Some(&0) {
             #[cfg(test)]
             info!("Pokémon disabled");
@@ -327,30 +431,46 @@ impl BotConfig {
         //     }
         // }
 
-        if !self.time.is_active(now)? {
-            if let Some(s) = self.time.bypass(iv, input.pokemon_level) {
-                write!(debug, "\nFiltro orario non attivo ma eccezione per {}", s).map_err(|_| ())?;
+        // out of hours without a bypass, in quiet mode it's collected as if active
+        let later = if active {
+            false
+        } else if let Some(s) = self.time.bypass(iv, input.pokemon_level, is_boosted(input)) {
+            write!(debug, "\nFiltro orario non attivo ma eccezione per {}", s).map_err(|_| ())?;
+            false
+        } else if quiet {
+            debug.push_str("\nFiltro orario non attivo, raccolto per il riepilogo");
+            true
+        } else {
+            #[cfg(test)]
+            info!(
+                "Pokémon discarded for time config: pokemon_id {} iv {:?} level {:?}",
+                pokemon_id, iv, input.pokemon_level
+            );
+
+            return Err(());
+        };
+
+        // the time bypass replaces the IV-Level config
+        if active || later {
+            if badge {
+                debug.push_str("\nEccezione per medaglia");
+            } else if let Some(s) =
+                BotPkmn::filter(filter, iv.as_ref(), input.pokemon_level.as_ref(), is_boosted(input))
+            {
+                if later {
+                    write!(debug, " con {}", s).map_err(|_| ())?;
+                } else {
+                    write!(debug, "\nFiltro orario attivo e {}", s).map_err(|_| ())?;
+                }
             } else {
                 #[cfg(test)]
                 info!(
-                    "Pokémon discarded for time config: pokemon_id {} iv {:?} level {:?}",
+                    "Pokémon discarded for IV-Level config: pokemon_id {} iv {:?} level {:?}",
                     pokemon_id, iv, input.pokemon_level
                 );
 
                 return Err(());
             }
-        } else if badge {
-            debug.push_str("\nEccezione per medaglia");
-        } else if let Some(s) = BotPkmn::filter(filter, iv.as_ref(), input.pokemon_level.as_ref()) {
-            write!(debug, "\nFiltro orario attivo e {}", s).map_err(|_| ())?;
-        } else {
-            #[cfg(test)]
-            info!(
-                "Pokémon discarded for IV-Level config: pokemon_id {} iv {:?} level {:?}",
-                pokemon_id, iv, input.pokemon_level
-            );
-
-            return Err(());
         }
 
         if !badge {
@@ -361,16 +481,27 @@ impl BotConfig {
             }
         }
 
-        Ok(PokemonMessage {
-            pokemon: input.clone(),
-            iv,
-            distance: BotLocs::calc_dist(&self.locs.h, pos)?,
-            direction: BotLocs::get_direction(&self.locs.h, pos)?,
-            debug: if self.debug == Some(true) { Some(debug) } else { None },
-        })
+        Ok(Delivery::new(
+            PokemonMessage {
+                pokemon: input.clone(),
+                iv,
+                distance: BotLocs::calc_dist(&self.locs.h, pos)?,
+                direction: BotLocs::get_direction(&self.locs.h, pos)?,
+                debug: if self.debug == Some(true) { Some(debug) } else { None },
+                overlay: self.get_overlay(loc, rad),
+            },
+            !later,
+        ))
     }
 
-    fn submit_raid(&self, now: &DateTime<Utc>, platform: &Platform, input: &Raid) -> Result<RaidMessage, ()> {
+    fn submit_raid(
+        &self,
+        now: &DateTime<Utc>,
+        platform: &Platform,
+        input: &Raid,
+        active: bool,
+        quiet: bool,
+    ) -> Result<Delivery<RaidMessage>, ()> {
         let pokemon_id = input.pokemon_id.and_then(|id| if id > 0 { Some(id.to_string()) } else { None });
         let loc = self.locs.get_raid_settings();
         let pos = (input.latitude, input.longitude);
@@ -394,7 +525,9 @@ impl BotConfig {
 
         let mut debug = format!("Scansione avvenuta{} alle {}\n", platform, now.with_timezone(&Rome).format("%T"));
         let dist = BotLocs::calc_dist(loc, pos)?;
-        if dist > rad {
+        if self.is_favorite_gym(&input.gym_id) {
+            write!(debug, "Palestra preferita, bypass distanza ({:.2} km)", dist).map_err(|_| ())?;
+        } else if dist > rad {
             #[cfg(test)]
             info!("Raid discarded for distance: loc {:?} pos {:?} dist {} rad {}", loc, pos, dist, rad);
 
@@ -403,7 +536,7 @@ impl BotConfig {
             write!(debug, "Distanza per Raid inferiore a {:.2} km ({:.2} km)", rad, dist).map_err(|_| ())?;
         }
 
-        if !self.time.is_active(now)? {
+        if !active && !quiet {
             #[cfg(test)]
             info!("Raid discarded for time config");
 
@@ -437,11 +570,27 @@ impl BotConfig {
             }
         }
 
-        Ok(RaidMessage {
-            raid: input.clone(),
-            distance: BotLocs::calc_dist(&self.locs.h, pos)?,
-            debug: if self.debug == Some(true) { Some(debug) } else { None },
-        })
+        if let Some(n) = self.raid.n.filter(|n| n.iter().any(|i| *i > 0)) {
+            write!(
+                debug,
+                "\nPromemoria: {} min prima della schiusa, alla schiusa {}, {} min prima della fine",
+                n[0],
+                if n[1] > 0 { "sì" } else { "no" },
+                n[2]
+            )
+            .map_err(|_| ())?;
+        }
+
+        Ok(Delivery::new(
+            RaidMessage {
+                raid: input.clone(),
+                distance: BotLocs::calc_dist(&self.locs.h, pos)?,
+                debug: if self.debug == Some(true) { Some(debug) } else { None },
+                reminders: self.raid.n,
+                overlay: self.get_overlay(loc, rad),
+            },
+            active,
+        ))
     }
 
     fn submit_pokestop(&self, now: &DateTime<Utc>, platform: &Platform, input: &Pokestop) -> Result<LureMessage, ()> {
@@ -457,7 +606,9 @@ impl BotConfig {
 
         let mut debug = format!("Scansione avvenuta{} alle {}\n", platform, now.with_timezone(&Rome).format("%T"));
         let dist = BotLocs::calc_dist(loc, pos)?;
-        if dist > rad {
+        if self.is_favorite_pokestop(&input.pokestop_id) {
+            write!(debug, "Pokéstop preferito, bypass distanza ({:.2} km)", dist).map_err(|_| ())?;
+        } else if dist > rad {
             return Err(());
         } else {
             write!(debug, "Distanza per Pokéstop inferiore a {:.2} km ({:.2} km)", rad, dist).map_err(|_| ())?;
@@ -475,7 +626,11 @@ impl BotConfig {
             debug.push_str("\nNessun filtro esche attivo");
         }
 
-        Ok(LureMessage { pokestop: input.clone(), debug: if self.debug == Some(true) { Some(debug) } else { None } })
+        Ok(LureMessage {
+            pokestop: input.clone(),
+            debug: if self.debug == Some(true) { Some(debug) } else { None },
+            overlay: self.get_overlay(loc, rad),
+        })
     }
 
     fn submit_invasion(
@@ -496,17 +651,22 @@ impl BotConfig {
 
         let mut debug = format!("Scansione avvenuta{} alle {}\n", platform, now.with_timezone(&Rome).format("%T"));
         let dist = BotLocs::calc_dist(loc, pos)?;
-        if dist > rad {
+        if self.is_favorite_pokestop(&input.pokestop_id) {
+            write!(debug, "Pokéstop preferito, bypass distanza ({:.2} km)", dist).map_err(|_| ())?;
+        } else if dist > rad {
             return Err(());
         } else {
             write!(debug, "Distanza per Pokéstop inferiore a {:.2} km ({:.2} km)", rad, dist).map_err(|_| ())?;
         }
 
         if invs.f == 1 {
-            if !invs.l.contains(input.get_grunt_type().as_ref().ok_or(())?) {
-                return Err(());
-            } else {
+            let grunt_type = input.get_grunt_type().ok_or(())?;
+            if invs.l.contains(&grunt_type) {
                 debug.push_str("\nScagnozzo presente nella lista degli scagnozzi abilitati");
+            } else if let Some((index, _)) = GRUNTS.load().get(&grunt_type).and_then(|grunt| invs.get_rule(grunt)) {
+                write!(debug, "\nScagnozzo abilitato dalla regola n. {}", index + 1).map_err(|_| ())?;
+            } else {
+                return Err(());
             }
         } else {
             debug.push_str("\nNessun filtro scagnozzi attivo");
@@ -515,10 +675,17 @@ impl BotConfig {
         Ok(InvasionMessage {
             invasion: input.clone(),
             debug: if self.debug == Some(true) { Some(debug) } else { None },
+            overlay: self.get_overlay(loc, rad),
         })
     }
 
-    fn submit_gym(&self, now: &DateTime<Utc>, platform: &Platform, input: &GymDetails) -> Result<GymMessage, ()> {
+    async fn submit_gym(
+        &self,
+        now: &DateTime<Utc>,
+        platform: &Platform,
+        input: &GymDetails,
+        active: bool,
+    ) -> Result<GymMessage, ()> {
         if self.raid.c != Some(1) {
             return Err(());
         }
@@ -538,7 +705,9 @@ impl BotConfig {
 
         let mut debug = format!("Scansione avvenuta{} alle {}\n", platform, now.with_timezone(&Rome).format("%T"));
         let dist = BotLocs::calc_dist(loc, pos)?;
-        if dist > rad {
+        if self.is_favorite_gym(&input.id) {
+            write!(debug, "Palestra preferita, bypass distanza ({:.2} km)", dist).map_err(|_| ())?;
+        } else if dist > rad {
             #[cfg(test)]
             info!("Gym discarded for distance: loc {:?} pos {:?} dist {} rad {}", loc, pos, dist, rad);
 
@@ -547,17 +716,53 @@ impl BotConfig {
             write!(debug, "Distanza per Palestre inferiore a {:.2} km ({:.2} km)", rad, dist).map_err(|_| ())?;
         }
 
-        if !self.time.is_active(now)? {
+        if !active {
             #[cfg(test)]
             info!("Gym discarded for time config");
 
             return Err(());
         }
 
+        let before = gyms::get_previous(input).await;
+        // without subscribed changes every update is notified
+        let events = match self.gym.as_ref().filter(|g| !g.e.is_empty()) {
+            Some(gym) => {
+                if gym.f == Some(1) && !self.is_favorite_gym(&input.id) {
+                    #[cfg(test)]
+                    info!("Gym discarded because isn't a favorite");
+
+                    return Err(());
+                }
+
+                let team = input.team.get_id();
+                let events = before
+                    .map(|b| gyms::get_events(&b, &input.into()))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|e| gym.e.contains(&e.get_id()))
+                    .filter(|e| *e != GymEvent::TeamChanged || gym.t.map(|t| t == team) != Some(false))
+                    .collect::<Vec<_>>();
+                if events.is_empty() {
+                    #[cfg(test)]
+                    info!("Gym discarded because no subscribed change happened");
+
+                    return Err(());
+                }
+                for event in &events {
+                    write!(debug, "\nCambiamento sottoscritto: {}", event.get_name()).map_err(|_| ())?;
+                }
+                events
+            }
+            None => Vec::new(),
+        };
+
         Ok(GymMessage {
             gym: input.clone(),
+            before,
+            events,
             distance: BotLocs::calc_dist(&self.locs.h, pos)?,
             debug: if self.debug == Some(true) { Some(debug) } else { None },
+            overlay: self.get_overlay(loc, rad),
         })
     }
 
@@ -568,20 +773,23 @@ impl BotConfig {
         weather: &Weather,
     ) -> Result<WeatherMessage, ()> {
         if let Some(user_id) = self.user_id.as_deref() {
-            let lock = WATCHES.read().await;
-            if let Some(watches) = lock.get(user_id) {
+            let mut lock = WATCHES.write().await;
+            if let Some(watches) = lock.get_mut(user_id) {
                 let timestamp = now.timestamp();
                 let time = now.with_timezone(&Rome).format("%T").to_string();
 
                 for watch in watches {
-                    if watch.expire < timestamp {
+                    if watch.watch.expire < timestamp {
                         continue;
                     }
 
-                    if weather.polygon.within(&watch.point) {
+                    // the same weather can be sent many times, notify only actual changes
+                    if weather.polygon.within(&watch.watch.point) && watch.weather != Some(weather.gameplay_condition) {
+                        let reference_weather = watch.weather.replace(weather.gameplay_condition);
                         return Ok(WeatherMessage {
-                            watch: watch.clone(),
-                            // actual_weather: weather.clone(),
+                            watch: watch.watch.clone(),
+                            reference_weather,
+                            actual_weather: weather.gameplay_condition,
                             debug: if self.debug == Some(true) { Some(time) } else { None },
                         });
                     }
@@ -744,6 +952,8 @@ pub struct BotRaid {
     pub l: Vec<u8>,
     pub p: Vec<PkmnRaid>,
     pub x: Option<u8>,
+    /// reminders: minutes before hatch, notification at hatch (0/1), minutes before end
+    pub n: Option<[u8; 3]>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -753,6 +963,29 @@ pub struct BotPkmn {
     pub p0: Option<u8>,
     #[serde(deserialize_with = "deserialize_list")]
     pub l: HashMap<String, Vec<u8>>,
+    /// rules applied to every Pokémon not listed in l
+    pub r: Option<Vec<PkmnRule>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PkmnRule {
+    /// minimum rarity
+    pub y: Option<u8>,
+    /// type
+    pub t: Option<String>,
+    /// generation
+    pub g: Option<u8>,
+    /// filter template, with the same layout of the explicit ones
+    pub f: Vec<u8>,
+}
+
+impl PkmnRule {
+    fn matches(&self, pokemon: &lists::Pokemon) -> bool {
+        self.y.map(|y| pokemon.rarity >= y) != Some(false)
+            && self.t.as_deref().map(|t| pokemon.has_type(t)) != Some(false)
+            && self.g.map(|g| pokemon.get_generation() == Some(g)) != Some(false)
+    }
 }
 
 #[derive(Deserialize)]
@@ -773,7 +1006,24 @@ where
     }
 }
 
+/// The webhook reports the boosting weather, or 0 when the spawn isn't boosted
+fn is_boosted(input: &Pokemon) -> bool {
+    matches!(input.weather, Some(w) if w > 0)
+}
+
+/// Species shown in game by a disguised spawn (e.g. Ditto), pokemon_id is always the true one
+pub fn get_disguise(input: &Pokemon) -> Option<u16> {
+    input.display_pokemon_id.filter(|id| *id > 0 && *id != input.pokemon_id)
+}
+
 impl BotPkmn {
+    /// First rule matching the given Pokémon
+    fn get_rule(&self, pokemon_id: u16) -> Option<(usize, &PkmnRule)> {
+        let list = LIST.load();
+        let pokemon = list.get(&pokemon_id)?;
+        self.r.as_ref()?.iter().enumerate().find(|(_, rule)| rule.matches(pokemon))
+    }
+
     /**
      * [1, 1, 100, 0, 25, 0, 10, 1]
      * 0: active
@@ -799,29 +1049,57 @@ impl BotPkmn {
      * 20: Mega
      * 21: ultra check
      * 22: Ultra
+     * 23: weather boost (1: only boosted, 2: only not boosted)
+     * 24: IV_min when boosted
+     * 25: LVL_min when boosted
+     * 26: PvP presets bitmask (see pvp::PRESETS, little and master league bits are ignored), OR'ed with 19-22 as every
+     *     advanced check: a Pokémon passing either the presets or the old rank checks is notified
+     * 27: fast move (first byte)
+     * 28: fast move (second byte)
+     * 29: charged move (first byte)
+     * 30: charged move (second byte)
+     * 31: CP_min (first byte)
+     * 32: CP_min (second byte)
+     * 33: CP_max (first byte)
+     * 34: CP_max (second byte)
+     * 35: sizes bitmask (1: XXS, 2: XS, 4: XL, 8: XXL)
+     * 36: disguise (1: only disguised, 2: only not disguised)
+     *
+     * Two bytes values are first + second * 255, not 256: forms have always been stored this way by the frontend,
+     * so moves and CP follow suit (e.g. 281 is [26, 1] and 255 is both [255, 0] and [0, 1])
      */
-    fn filter(filter: &[u8], iv: Option<&u8>, lvl: Option<&u8>) -> Option<String> {
+    fn filter(filter: &[u8], iv: Option<&u8>, lvl: Option<&u8>, boosted: bool) -> Option<String> {
+        // boosted spawns can have their own thresholds
+        let (iv_min, lvl_min, suffix) = match (filter.get(24), filter.get(25)) {
+            (Some(i), Some(l)) if boosted && (*i > 0 || *l > 0) => (
+                if *i > 0 { Some(i) } else { filter.get(2) },
+                if *l > 0 { Some(l) } else { filter.get(4) },
+                " (boost meteo)",
+            ),
+            _ => (filter.get(2), filter.get(4), ""),
+        };
         if filter.get(1) >= Some(&1) && filter.get(3) == Some(&1) {
             // IV e PL attivi
             if filter.get(7) == Some(&1) {
-                if iv >= filter.get(2) || lvl >= filter.get(4) {
+                if iv >= iv_min || lvl >= lvl_min {
                     return Some(format!(
-                        "IV >= {} O LVL >= {}",
-                        filter.get(2).unwrap_or(&0),
-                        filter.get(4).unwrap_or(&0)
+                        "IV >= {} O LVL >= {}{}",
+                        iv_min.unwrap_or(&0),
+                        lvl_min.unwrap_or(&0),
+                        suffix
                     ));
                 }
-            } else if iv >= filter.get(2) && lvl >= filter.get(4) {
-                return Some(format!("IV >= {} E LVL >= {}", filter.get(2).unwrap_or(&0), filter.get(4).unwrap_or(&0)));
+            } else if iv >= iv_min && lvl >= lvl_min {
+                return Some(format!("IV >= {} E LVL >= {}{}", iv_min.unwrap_or(&0), lvl_min.unwrap_or(&0), suffix));
             }
             None
         } else if filter.get(1) >= Some(&1) || filter.get(3) == Some(&1) {
             // IV o PL attivi
-            if filter.get(1) >= Some(&1) && iv >= filter.get(2) {
-                return Some(format!("IV >= {}", filter.get(2).unwrap_or(&0)));
+            if filter.get(1) >= Some(&1) && iv >= iv_min {
+                return Some(format!("IV >= {}{}", iv_min.unwrap_or(&0), suffix));
             }
-            if filter.get(3) == Some(&1) && lvl >= filter.get(4) {
-                return Some(format!("LVL >= {}", filter.get(4).unwrap_or(&0)));
+            if filter.get(3) == Some(&1) && lvl >= lvl_min {
+                return Some(format!("LVL >= {}{}", lvl_min.unwrap_or(&0), suffix));
             }
             None
         } else {
@@ -854,6 +1132,24 @@ impl BotPkmn {
      * 20: Mega
      * 21: ultra check
      * 22: Ultra
+     * 23: weather boost (1: only boosted, 2: only not boosted)
+     * 24: IV_min when boosted
+     * 25: LVL_min when boosted
+     * 26: PvP presets bitmask (see pvp::PRESETS, little and master league bits are ignored), OR'ed with 19-22 as every
+     *     advanced check: a Pokémon passing either the presets or the old rank checks is notified
+     * 27: fast move (first byte)
+     * 28: fast move (second byte)
+     * 29: charged move (first byte)
+     * 30: charged move (second byte)
+     * 31: CP_min (first byte)
+     * 32: CP_min (second byte)
+     * 33: CP_max (first byte)
+     * 34: CP_max (second byte)
+     * 35: sizes bitmask (1: XXS, 2: XS, 4: XL, 8: XXL)
+     * 36: disguise (1: only disguised, 2: only not disguised)
+     *
+     * Two bytes values are first + second * 255, not 256: forms have always been stored this way by the frontend,
+     * so moves and CP follow suit (e.g. 281 is [26, 1] and 255 is both [255, 0] and [0, 1])
      */
     fn check_badge(filter: &[u8], input: &Pokemon) -> bool {
         if filter.get(8) == Some(&1) {
@@ -906,6 +1202,24 @@ impl BotPkmn {
      * 20: Mega
      * 21: ultra check
      * 22: Ultra
+     * 23: weather boost (1: only boosted, 2: only not boosted)
+     * 24: IV_min when boosted
+     * 25: LVL_min when boosted
+     * 26: PvP presets bitmask (see pvp::PRESETS, little and master league bits are ignored), OR'ed with 19-22 as every
+     *     advanced check: a Pokémon passing either the presets or the old rank checks is notified
+     * 27: fast move (first byte)
+     * 28: fast move (second byte)
+     * 29: charged move (first byte)
+     * 30: charged move (second byte)
+     * 31: CP_min (first byte)
+     * 32: CP_min (second byte)
+     * 33: CP_max (first byte)
+     * 34: CP_max (second byte)
+     * 35: sizes bitmask (1: XXS, 2: XS, 4: XL, 8: XXL)
+     * 36: disguise (1: only disguised, 2: only not disguised)
+     *
+     * Two bytes values are first + second * 255, not 256: forms have always been stored this way by the frontend,
+     * so moves and CP follow suit (e.g. 281 is [26, 1] and 255 is both [255, 0] and [0, 1])
      */
     fn advanced_filters(filter: &[u8], input: &Pokemon) -> Result<Option<String>, ()> {
         if filter.get(16) == Some(&1)
@@ -918,6 +1232,59 @@ impl BotPkmn {
 
         let mut dbg = String::new();
 
+        match filter.get(23) {
+            Some(&1) => {
+                if !is_boosted(input) {
+                    #[cfg(test)]
+                    info!("{} Pokémon discarded for Advanced Filters config: isn't boosted", input.encounter_id);
+
+                    return Ok(None);
+                } else {
+                    dbg.push_str("\nFiltro avanzato: Boost meteo");
+                }
+            }
+            Some(&2) => {
+                if is_boosted(input) {
+                    #[cfg(test)]
+                    info!("{} Pokémon discarded for Advanced Filters config: is boosted", input.encounter_id);
+
+                    return Ok(None);
+                } else {
+                    dbg.push_str("\nFiltro avanzato: Senza boost meteo");
+                }
+            }
+            _ => {}
+        }
+
+        match filter.get(36) {
+            Some(&1) => {
+                if let Some(disguise) = get_disguise(input) {
+                    write!(
+                        dbg,
+                        "\nFiltro avanzato: Travestito da {}",
+                        LIST.load().get(&disguise).map(|p| p.name.as_str()).unwrap_or_default()
+                    )
+                    .map_err(|_| ())?;
+                } else {
+                    #[cfg(test)]
+                    info!("{} Pokémon discarded for Advanced Filters config: isn't disguised", input.encounter_id);
+
+                    return Ok(None);
+                }
+            }
+            Some(&2) => {
+                if get_disguise(input).is_some() {
+                    #[cfg(test)]
+                    info!("{} Pokémon discarded for Advanced Filters config: is disguised", input.encounter_id);
+
+                    return Ok(None);
+                } else {
+                    dbg.push_str("\nFiltro avanzato: Non travestito");
+                }
+            }
+            _ => {}
+        }
+
         match filter.get(9) {
             Some(&1) => {
                 if input.gender != Gender::Male {
@@ -965,6 +1332,76 @@ impl BotPkmn {
             }
         }
 
+        // values not fitting a byte are split like forms, with 255 as radix
+        let get_u16 = |index: usize| {
+            filter
+                .get(index)
+                .map(|lo| *lo as u16 + filter.get(index + 1).map(|hi| *hi as u16 * 255).unwrap_or_default())
+        };
+
+        for (index, input_move, kind) in [(27, input.move_1, "veloce"), (29, input.move_2, "caricata")] {
+            if let Some(m) = get_u16(index).filter(|m| *m > 0) {
+                if Some(m) != input_move {
+                    #[cfg(test)]
+                    info!("{} Pokémon discarded for Advanced Filters config: wrong {} move", input.encounter_id, kind);
+
+                    return Ok(None);
+                } else {
+                    let moves = MOVES.load();
+                    write!(
+                        dbg,
+                        "\nFiltro avanzato: Mossa {} {}",
+                        kind,
+                        moves.get(&m).map(|m| m.as_str()).unwrap_or_else(|| "<sconosciuta>")
+                    )
+                    .map_err(|_| ())?;
+                }
+            }
+        }
+
+        if let Some(mask) = filter.get(35).filter(|m| **m > 0) {
+            let size = LIST.load().get(&input.pokemon_id).and_then(|p| p.get_size(input.height, input.weight));
+            match size {
+                Some(size) if size.get_bit() & mask > 0 => {
+                    write!(dbg, "\nFiltro avanzato: Taglia {}", size.get_name()).map_err(|_| ())?;
+                }
+                _ => {
+                    #[cfg(test)]
+                    info!("{} Pokémon discarded for Advanced Filters config: size {:?}", input.encounter_id, size);
+
+                    return Ok(None);
+                }
+            }
+        }
+
+        let cp_min = get_u16(31).filter(|cp| *cp > 0);
+        let cp_max = get_u16(33).filter(|cp| *cp > 0);
+        if cp_min.is_some() || cp_max.is_some() {
+            match input.cp {
+                Some(cp)
+                    if cp_min.map(|min| cp >= min) != Some(false) && cp_max.map(|max| cp <= max) != Some(false) =>
+                {
+                    write!(
+                        dbg,
+                        "\nFiltro avanzato: PL {} tra {} e {}",
+                        cp,
+                        cp_min.unwrap_or_default(),
+                        cp_max.map(|max| max.to_string()).unwrap_or_else(|| String::from("-"))
+                    )
+                    .map_err(|_| ())?;
+                }
+                _ => {
+                    #[cfg(test)]
+                    info!(
+                        "{} Pokémon discarded for Advanced Filters config: CP {:?} out of range",
+                        input.encounter_id, input.cp
+                    );
+
+                    return Ok(None);
+                }
+            }
+        }
+
         let filter_rank = |check: Option<&u8>, filter: Option<&u8>, pvp: Option<&[PvpRanking]>| {
             match check {
                 Some(&1) => {
@@ -1153,68 +1590,62 @@ impl BotPkmn {
             }
         };
 
+        let rank_check = |check: Option<Option<PvpRanking>>| match check {
+            Some(Some(rank)) => rank_to_string(rank).map(|s| Some(Some(s))),
+            Some(None) => Ok(Some(None)),
+            None => Ok(None),
+        };
+
+        let filter_presets = |mask: Option<&u8>| match mask.map(|mask| pvp::get_supported(*mask)) {
+            Some(mask) if mask > 0 => match pvp::check_presets(mask, input) {
+                Some((league, top, rank)) => {
+                    rank_to_string(rank).map(|s| Some(Some(format!(" {} top {}{}", league.get_name(), top, s))))
+                }
+                None => Ok(Some(None)),
+            },
+            _ => Ok(None),
+        };
+
         // filters are in OR condition
         // None => not checked
         // Some(None) => check failed
         // Some(Some(s)) => check passed
-        match (
-            filter_rank(filter.get(19), filter.get(20), input.pvp_rankings_great_league.as_deref()),
-            filter_rank(filter.get(21), filter.get(22), input.pvp_rankings_ultra_league.as_deref()),
-            filter_iv(
-                filter.get(10),
-                filter.get(11),
-                filter.get(12),
-                filter.get(13),
-                filter.get(14),
-                filter.get(15),
-                input.individual_attack.as_ref(),
-                input.individual_defense.as_ref(),
-                input.individual_stamina.as_ref(),
-            )?,
-        ) {
-            (Some(Some(mega)), Some(Some(ultra)), Some(Some(s))) => {
-                write!(
-                    dbg,
-                    "\nFiltro avanzato: Mega{}\nFiltro avanzato: Ultra{}\nFiltro avanzato: IV{}",
-                    rank_to_string(mega)?,
-                    rank_to_string(ultra)?,
-                    s,
-                )
-                .map_err(|_| ())?;
-            }
-            (Some(Some(mega)), Some(Some(ultra)), _) => {
-                write!(
-                    dbg,
-                    "\nFiltro avanzato: Mega{}\nFiltro avanzato: Ultra{}",
-                    rank_to_string(mega)?,
-                    rank_to_string(ultra)?,
-                )
-                .map_err(|_| ())?;
-            }
-            (Some(Some(mega)), _, Some(Some(s))) => {
-                write!(dbg, "\nFiltro avanzato: Mega{}\nFiltro avanzato: IV{}", rank_to_string(mega)?, s,)
-                    .map_err(|_| ())?;
-            }
-            (Some(Some(mega)), _, _) => {
-                write!(dbg, "\nFiltro avanzato: Mega{}", rank_to_string(mega)?,).map_err(|_| ())?;
-            }
-            (_, Some(Some(ultra)), Some(Some(s))) => {
-                write!(dbg, "\nFiltro avanzato: Ultra{}\nFiltro avanzato: IV{}", rank_to_string(ultra)?, s,)
-                    .map_err(|_| ())?;
-            }
-            (_, Some(Some(ultra)), _) => {
-                write!(dbg, "\nFiltro avanzato: Ultra{}", rank_to_string(ultra)?,).map_err(|_| ())?;
-            }
-            (_, _, Some(Some(s))) => {
-                write!(dbg, "\nFiltro avanzato: IV{}", s).map_err(|_| ())?;
+        let checks = [
+            (
+                "Mega",
+                rank_check(filter_rank(filter.get(19), filter.get(20), input.pvp_rankings_great_league.as_deref()))?,
+            ),
+            (
+                "Ultra",
+                rank_check(filter_rank(filter.get(21), filter.get(22), input.pvp_rankings_ultra_league.as_deref()))?,
+            ),
+            ("PvP", filter_presets(filter.get(26))?),
+            (
+                "IV",
+                filter_iv(
+                    filter.get(10),
+                    filter.get(11),
+                    filter.get(12),
+                    filter.get(13),
+                    filter.get(14),
+                    filter.get(15),
+                    input.individual_attack.as_ref(),
+                    input.individual_defense.as_ref(),
+                    input.individual_stamina.as_ref(),
+                )?,
+            ),
+        ];
+        if checks.iter().any(|(_, c)| matches!(c, Some(Some(_)))) {
+            for (name, s) in
+                checks.iter().filter_map(|(name, c)| c.as_ref().and_then(|s| s.as_ref().map(|s| (name, s))))
+            {
+                write!(dbg, "\nFiltro avanzato: {}{}", name, s).map_err(|_| ())?;
             }
-            (None, None, None) => {}
-            (Some(None), _, _) | (_, Some(None), _) | (_, _, Some(None)) => {
-                #[cfg(test)]
-                info!("{:?} Pokémon discarded for Advanced Filters config", serde_json::to_string(input));
+        } else if checks.iter().any(|(_, c)| c.is_some()) {
+            #[cfg(test)]
+            info!("{:?} Pokémon discarded for Advanced Filters config", serde_json::to_string(input));
 
-                return Ok(None);
-            }
+            return Ok(None);
         }
 
         Ok(Some(dbg))
@@ -1282,17 +1713,38 @@ pub struct BotTime {
     pub fl: [u8; 2],
     pub fc: u8,
     pub ex: Option<u8>,
+    #[serde(default)]
     pub w1: Vec<u8>,
+    #[serde(default)]
     pub w2: Vec<u8>,
     pub w3: Option<Vec<u8>>,
     pub w4: Option<Vec<u8>>,
     pub w5: Option<Vec<u8>>,
     pub w6: Option<Vec<u8>>,
     pub w7: Option<Vec<u8>>,
+    /// IV and level thresholds replacing fi and fl for weather boosted spawns
+    pub fb: Option<[u8; 2]>,
+    /// out of hours notifications are collected and sent when the next active window starts
+    pub q: Option<u8>,
+    /// minute resolution schedule, replacing the hour lists when present
+    pub s: Option<BotSchedule>,
+    /// timestamp until which every notification is silenced, time bypasses and quiet hours summary included
+    pub z: Option<i64>,
 }
 
 impl BotTime {
+    fn is_snoozed(&self, now: &DateTime<Utc>) -> bool {
+        self.z.map(|z| now.timestamp() < z) == Some(true)
+    }
+
     fn is_active(&self, now: &DateTime<Utc>) -> Result<bool, ()> {
+        if self.is_snoozed(now) {
+            return Ok(false);
+        }
+        if let Some(schedule) = &self.s {
+            return Ok(schedule.is_active(now));
+        }
+
         let hour: u8 = now
             .with_timezone(&Rome)
             .format("%H")
@@ -1318,25 +1770,34 @@ impl BotTime {
         }
     }
 
-    fn bypass(&self, iv: Option<u8>, lvl: Option<u8>) -> Option<String> {
+    /// Out of hours IV or level thresholds are enabled
+    fn has_bypass(&self) -> bool {
+        self.fi[0] != 0 || self.fl[0] != 0
+    }
+
+    fn bypass(&self, iv: Option<u8>, lvl: Option<u8>, boosted: bool) -> Option<String> {
+        let (iv_min, lvl_min, suffix) = match self.fb {
+            Some(fb) if boosted => (fb[0], fb[1], " (boost meteo)"),
+            _ => (self.fi[1], self.fl[1], ""),
+        };
         if self.fi[0] == 1 && self.fl[0] == 1 {
             if self.fc == 1 {
-                if iv >= Some(self.fi[1]) || lvl >= Some(self.fl[1]) {
-                    return Some(format!("IV >= {} O LVL >= {}", self.fi[1], self.fl[1]));
+                if iv >= Some(iv_min) || lvl >= Some(lvl_min) {
+                    return Some(format!("IV >= {} O LVL >= {}{}", iv_min, lvl_min, suffix));
                 }
                 None
             } else {
-                if iv >= Some(self.fi[1]) && lvl >= Some(self.fl[1]) {
-                    return Some(format!("IV >= {} E LVL >= {}", self.fi[1], self.fl[1]));
+                if iv >= Some(iv_min) && lvl >= Some(lvl_min) {
+                    return Some(format!("IV >= {} E LVL >= {}{}", iv_min, lvl_min, suffix));
                 }
                 None
             }
         } else {
-            if self.fi[0] == 1 && iv >= Some(self.fi[1]) {
-                return Some(format!("IV >= {}", self.fi[1]));
+            if self.fi[0] == 1 && iv >= Some(iv_min) {
+                return Some(format!("IV >= {}{}", iv_min, suffix));
             }
-            if self.fl[0] == 1 && lvl >= Some(self.fl[1]) {
-                return Some(format!("LVL >= {}", self.fl[1]));
+            if self.fl[0] == 1 && lvl >= Some(lvl_min) {
+                return Some(format!("LVL >= {}{}", lvl_min, suffix));
             }
 
             None
@@ -1344,6 +1805,73 @@ impl BotTime {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotSchedule {
+    /// intervals for every weekday starting from monday, as [start, end) minutes from midnight,
+    /// continuing into the next day when start > end
+    pub d: [Vec<[u16; 2]>; 7],
+    /// date ranges replacing the weekly intervals, like holidays or vacations
+    #[serde(default)]
+    pub o: Vec<BotOverride>,
+}
+
+impl BotSchedule {
+    fn get_intervals(&self, day: NaiveDate) -> &[[u16; 2]] {
+        let date = day.year() as u32 * 10000 + day.month() * 100 + day.day();
+        match self.o.iter().find(|o| o.f <= date && date <= o.t) {
+            Some(o) => &o.i,
+            None => &self.d[day.weekday().num_days_from_monday() as usize],
+        }
+    }
+
+    fn is_active(&self, now: &DateTime<Utc>) -> bool {
+        let local = now.with_timezone(&Rome);
+        let minute = (local.hour() * 60 + local.minute()) as u16;
+        let today = local.date_naive();
+        // intervals crossing midnight end in the morning of the next day
+        self.get_intervals(today).iter().any(|[start, end]| *start <= minute && (start > end || minute < *end))
+            || today
+                .pred_opt()
+                .map(|yesterday| self.get_intervals(yesterday).iter().any(|[start, end]| start > end && minute < *end))
+                == Some(true)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotOverride {
+    /// first day, as YYYYMMDD
+    pub f: u32,
+    /// last day, as YYYYMMDD
+    pub t: u32,
+    /// intervals for these days, empty when notifications are off
+    #[serde(default)]
+    pub i: Vec<[u16; 2]>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotGym {
+    /// subscribed changes, 1: team changed, 2: slot opened, 3: under attack
+    pub e: Vec<u8>,
+    /// team changes only towards this team
+    pub t: Option<u8>,
+    /// only favorite gyms (0/1)
+    pub f: Option<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotFavs {
+    /// gym ids, notified regardless of distance
+    #[serde(default)]
+    pub g: Vec<String>,
+    /// pokéstop ids, notified regardless of distance
+    #[serde(default)]
+    pub s: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct BotQest {
@@ -1357,6 +1885,49 @@ pub struct BotPkst {
     pub n: u8,
     pub f: u8,
     pub l: Vec<u16>,
+    /// rules applied to every grunt not listed in l
+    pub r: Option<Vec<InvsRule>>,
+}
+
+impl BotPkst {
+    /// First rule matching the given grunt
+    fn get_rule(&self, grunt: &lists::GruntType) -> Option<(usize, &InvsRule)> {
+        self.r.as_ref()?.iter().enumerate().find(|(_, rule)| rule.matches(grunt))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InvsRule {
+    /// element type
+    pub e: Option<String>,
+    /// grunt gender
+    pub s: Option<String>,
+    /// kind, 1: grunt, 2: leader, 3: Giovanni
+    pub k: Option<u8>,
+    /// reward Pokémon
+    pub p: Option<u16>,
+}
+
+impl InvsRule {
+    fn matches(&self, grunt: &lists::GruntType) -> bool {
+        let kind = if grunt.is_giovanni() {
+            3
+        } else if grunt.is_leader() {
+            2
+        } else {
+            1
+        };
+        let same = |rule: &Option<String>, value: &Option<String>| match (rule, value) {
+            (Some(r), Some(v)) => r.eq_ignore_ascii_case(v),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        same(&self.e, &grunt.element)
+            && same(&self.s, &grunt.sex)
+            && self.k.map(|k| k == kind) != Some(false)
+            && self.p.map(|p| grunt.get_rewards().any(|id| id == p)) != Some(false)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1364,6 +1935,12 @@ pub struct BotPkst {
 pub struct BotMore {
     #[serde(default = "default_more_l")]
     pub l: String,
+    /// delete notifications from the chat once expired (0/1)
+    pub d: Option<u8>,
+    /// draw position and radius on maps (0/1)
+    pub m: Option<u8>,
+    /// minutes Pokémon notifications are collected and sent together, 0 disables the digest
+    pub g: Option<u16>,
 }
 
 fn default_more_l() -> String {
@@ -1382,6 +1959,9 @@ mod tests {
 
     use crate::bot::Request;
 
+    const POKEMON_CONFIG: &str = r#"{"locs":{"h":["43.787206","11.252832"],"p":["43.781134","11.259613","7"],"r":["43.778035","11.259184","7"],"i":["43.778159","11.259098","7"],"t_p":["0","0","0"],"t_r":["0","0","0"],"t_i":["","",""]},"raid":{"u":0,"s":0,"x":0,"l":[5],"p":[-5]},"pkmn":{"l":{"1":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"2":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"3":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"4":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"5":[1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,1,99,1,99],"6":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"7":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"8":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"9":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"10":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"11":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"12":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"15":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"18":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"19":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"20":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"21":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"22":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"23":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"24":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"25":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"26":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"27":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"28":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"29":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"30":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"31":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"32":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"33":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"34":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"35":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"36":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"39":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"40":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"41":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"42":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"45":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"46":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"47":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"48":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"49":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"50":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"51":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"52":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"53":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"54":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"55":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"56":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"57":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"58":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"59":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"61":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"62":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"65":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"66":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"67":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"68":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"69":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"70":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"71":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"72":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"73":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"74":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"75":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"76":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"77":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"78":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"79":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"80":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"83":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"84":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"85":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"88":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"89":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"90":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"91":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"92":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"93":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"94":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"95":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"96":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"97":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"98":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"99":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"102":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"103":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"104":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"105":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"106":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"107":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"108":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"109":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"110":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"111":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"112":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"113":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"114":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"116":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"117":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"118":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"119":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"120":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"121":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"122":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"123":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"124":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"125":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"126":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"127":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"130":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"131":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"132":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"133":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"134":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"135":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"136":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"137":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"138":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"139":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"140":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"141":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"143":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"149":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"152":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"153":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"154":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"155":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"156":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"157":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"158":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"159":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"160":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"163":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"164":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"167":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"168":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"169":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"170":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"171":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"176":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"177":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"178":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"179":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"180":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"181":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"183":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"184":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"185":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"187":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"188":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"189":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"190":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"191":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"194":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"195":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"198":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"200":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"201":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"202":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"203":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"204":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"205":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"206":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"207":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"209":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"210":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"211":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"213":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"215":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"216":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"217":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"219":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"220":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"221":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"222":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"223":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"224":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"225":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"226":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"227":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"229":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"231":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"232":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"234":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"237":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"241":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"242":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"246":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"247":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"248":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"252":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"253":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"254":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"255":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"256":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"257":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"258":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"259":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"260":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"261":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"262":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"263":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"264":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"265":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"270":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"271":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"272":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"273":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"274":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"275":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"276":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"277":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"278":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"279":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"280":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"281":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"283":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"284":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"285":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"287":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"288":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"289":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"290":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"295":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"296":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"297":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"299":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"301":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"302":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"304":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"305":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"306":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"308":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"309":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"310":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"311":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"312":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"313":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"314":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"318":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"319":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"320":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"322":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"323":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"325":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"326":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"328":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"329":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"330":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"331":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"332":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"333":[1,0,0,0,0,0,0,0,0,0,2,3,2,11,2,11,1,0,0,1,99,1,99],"335":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"336":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"337":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"339":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"340":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"341":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"342":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"343":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"345":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"346":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"347":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"348":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"349":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"353":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"354":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"356":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"357":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"358":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"359":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"361":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"362":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"364":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"366":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"370":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"371":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"372":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"373":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"374":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"375":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"376":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"387":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"388":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"391":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"393":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"394":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"397":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"399":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"400":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"401":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"402":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"408":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"410":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"412":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"415":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"418":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"419":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"420":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"421":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"422":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"426":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"427":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"428":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"431":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"432":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"434":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"435":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"436":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"437":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"441":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"443":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"444":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"449":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"450":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"452":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"453":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"456":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"457":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"459":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"460":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"481":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"495":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"496":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"498":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"499":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"501":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"502":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"506":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"507":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"509":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"513":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"515":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"520":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"522":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"524":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"529":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"531":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"535":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"538":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"540":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"543":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"546":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"548":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"550":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"554":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"557":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"562":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"564":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"566":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"568":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"572":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"574":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"577":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"580":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"585":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"587":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"588":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"594":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"597":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"605":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"607":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"608":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"610":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"611":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"613":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"615":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"616":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"618":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"622":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"631":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"632":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"633":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"634":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"650":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"653":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"656":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"661":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"667":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"714":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99]}},"time":{"fi":[0,80],"fl":[0,30],"fc":0,"w1":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23],"w2":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23]},"invs":{"n":0,"f":0,"l":[41,42,43,44]},"more":{"l":"g"},"debug":true}"#;
+    const POKEMON: &str = r#"{"pokestop_id":"3d716717cc65421490684ef9b213a382.16","disappear_time":1571079918,"cp":null,"form":0,"move_1":null,"longitude":11.241531239206385,"costume":0,"pokemon_id":656,"disappear_time_verified":false,"gender":1,"individual_attack":0,"spawnpoint_id":"None","latitude":43.771913285587665,"pokemon_level":15,"move_2":null,"individual_defense":15,"weight":null,"encounter_id":"12661125248363616471","height":null,"weather":1,"first_seen":1571078718,"individual_stamina":15,"last_modified_time":1571078718,"pvp_rankings_great_league":[{"rank":809,"percentage":0.8700643398554556,"level":40.0,"form":0,"cp":989,"pokemon":255},{"rank":351,"percentage":0.9907850542156611,"level":38.5,"pokemon":256,"form":0,"cp":1490},{"pokemon":257,"form":0,"percentage":null,"cp":null,"level":null,"rank":null}]}"#;
+
     #[test]
     fn bot_config() {
         tracing_subscriber::fmt::try_init().ok();
@@ -1416,14 +1996,41 @@ mod tests {
     async fn pokemon_ok() {
         tracing_subscriber::fmt::try_init().ok();
 
-        let config = serde_json::from_str::<BotConfig>(r#"{"locs":{"h":["43.787206","11.252832"],"p":["43.781134","11.259613","7"],"r":["43.778035","11.259184","7"],"i":["43.778159","11.259098","7"],"t_p":["0","0","0"],"t_r":["0","0","0"],"t_i":["","",""]},"raid":{"u":0,"s":0,"x":0,"l":[5],"p":[-5]},"pkmn":{"l":{"1":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"2":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"3":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"4":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"5":[1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,1,99,1,99],"6":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"7":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"8":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"9":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"10":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"11":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"12":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"15":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"18":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"19":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"20":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"21":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"22":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"23":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"24":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"25":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"26":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"27":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"28":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"29":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"30":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"31":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"32":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"33":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"34":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"35":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"36":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"39":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"40":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"41":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"42":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"45":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"46":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"47":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"48":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"49":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"50":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"51":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"52":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"53":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"54":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"55":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"56":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"57":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"58":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"59":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"61":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"62":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"65":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"66":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"67":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"68":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"69":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"70":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"71":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"72":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"73":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"74":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"75":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"76":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"77":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"78":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"79":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"80":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"83":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"84":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"85":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"88":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"89":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"90":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"91":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"92":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"93":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"94":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"95":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"96":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"97":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"98":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"99":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"102":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"103":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"104":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"105":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"106":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"107":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"108":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"109":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"110":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"111":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"112":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"113":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"114":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"116":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"117":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"118":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"119":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"120":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"121":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"122":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"123":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"124":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"125":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"126":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"127":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"130":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"131":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"132":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"133":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"134":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"135":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"136":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"137":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"138":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"139":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"140":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"141":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"143":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"149":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"152":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"153":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"154":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"155":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"156":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"157":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"158":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"159":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"160":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"163":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"164":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"167":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"168":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"169":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"170":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"171":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"176":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"177":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"178":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"179":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"180":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"181":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"183":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"184":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"185":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"187":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"188":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"189":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"190":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"191":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"194":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"195":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"198":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"200":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"201":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"202":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"203":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"204":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"205":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"206":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"207":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"209":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"210":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"211":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"213":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"215":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"216":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"217":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"219":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"220":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"221":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"222":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"223":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"224":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"225":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"226":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"227":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"229":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"231":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"232":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"234":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"237":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"241":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"242":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"246":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"247":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"248":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"252":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"253":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"254":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"255":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"256":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"257":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"258":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"259":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"260":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"261":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"262":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"263":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"264":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"265":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"270":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"271":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"272":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"273":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"274":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"275":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"276":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"277":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"278":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"279":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"280":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"281":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"283":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"284":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"285":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"287":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"288":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"289":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"290":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"295":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"296":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"297":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"299":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"301":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"302":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"304":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"305":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"306":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"308":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"309":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"310":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"311":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"312":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"313":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"314":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"318":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"319":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"320":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"322":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"323":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"325":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"326":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"328":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"329":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"330":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"331":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"332":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"333":[1,0,0,0,0,0,0,0,0,0,2,3,2,11,2,11,1,0,0,1,99,1,99],"335":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"336":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"337":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"339":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"340":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"341":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"342":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"343":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"345":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"346":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"347":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"348":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"349":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"353":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"354":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"356":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"357":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"358":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"359":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"361":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"362":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"364":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"366":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"370":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"371":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"372":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"373":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"374":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"375":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"376":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"387":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"388":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"391":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"393":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"394":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"397":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"399":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"400":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"401":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"402":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"408":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"410":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"412":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"415":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"418":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"419":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"420":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"421":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"422":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"426":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"427":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"428":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"431":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"432":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"434":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"435":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"436":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"437":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"441":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"443":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"444":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"449":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"450":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"452":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"453":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"456":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"457":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"459":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"460":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"481":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"495":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"496":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"498":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"499":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"501":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"502":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"506":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"507":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"509":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"513":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"515":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"520":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"522":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"524":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"529":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"531":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"535":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"538":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"540":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"543":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"546":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"548":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"550":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"554":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"557":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"562":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"564":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"566":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"568":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"572":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"574":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"577":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"580":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"585":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"587":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"588":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"594":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"597":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"605":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"607":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"608":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"610":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"611":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"613":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"615":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"616":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"618":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"622":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"631":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"632":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"633":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"634":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"650":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"653":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"656":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"661":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"667":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99],"714":[1,0,0,0,0,0,0,0,0,0,2,0,2,0,2,0,1,0,0,1,99,1,99]}},"time":{"fi":[0,80],"fl":[0,30],"fc":0,"w1":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23],"w2":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23]},"invs":{"n":0,"f":0,"l":[41,42,43,44]},"more":{"l":"g"},"debug":true}"#).unwrap();
-        let input: Pokemon = serde_json::from_str(r#"{"pokestop_id":"3d716717cc65421490684ef9b213a382.16","disappear_time":1571079918,"cp":null,"form":0,"move_1":null,"longitude":11.241531239206385,"costume":0,"pokemon_id":656,"disappear_time_verified":false,"gender":1,"individual_attack":0,"spawnpoint_id":"None","latitude":43.771913285587665,"pokemon_level":15,"move_2":null,"individual_defense":15,"weight":null,"encounter_id":"12661125248363616471","height":null,"weather":1,"first_seen":1571078718,"individual_stamina":15,"last_modified_time":1571078718,"pvp_rankings_great_league":[{"rank":809,"percentage":0.8700643398554556,"level":40.0,"form":0,"cp":989,"pokemon":255},{"rank":351,"percentage":0.9907850542156611,"level":38.5,"pokemon":256,"form":0,"cp":1490},{"pokemon":257,"form":0,"percentage":null,"cp":null,"level":null,"rank":null}]}"#).unwrap();
+        let config = serde_json::from_str::<BotConfig>(POKEMON_CONFIG).unwrap();
+        let input: Pokemon = serde_json::from_str(POKEMON).unwrap();
         assert!(config
             .submit(&Utc::now(), &Platform::Unknown, &Request::Pokemon(Box::new(input.into())))
             .await
             .is_ok());
     }
 
+    #[tokio::test]
+    async fn quiet_hours() {
+        let input: Pokemon = serde_json::from_str(POKEMON).unwrap();
+        let request = Request::Pokemon(Box::new(input.into()));
+        let all_day = r#""w1":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23],"w2":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23]"#;
+        let submit = |fi: &str, time: &str| {
+            let json = POKEMON_CONFIG.replace(r#""fi":[0,80]"#, fi).replace(all_day, time);
+            let config = serde_json::from_str::<BotConfig>(&json).unwrap();
+            let request = &request;
+            async move { config.submit(&Utc::now(), &Platform::Unknown, request).await }
+        };
+        let no_bypass = r#""fi":[0,80]"#;
+        let bypass = r#""fi":[1,0]"#;
+
+        assert!(matches!(submit(no_bypass, all_day).await, Ok(super::Delivery::Now(_))));
+        // out of hours it's discarded, unless collected in quiet mode or bypassed
+        assert!(submit(no_bypass, r#""w1":[],"w2":[]"#).await.is_err());
+        assert!(matches!(submit(no_bypass, r#""w1":[],"w2":[],"q":1"#).await, Ok(super::Delivery::Away(_))));
+        assert!(matches!(submit(bypass, r#""w1":[],"w2":[]"#).await, Ok(super::Delivery::Now(_))));
+
+        // snooze silences everything
+        let snooze = format!(r#""z":{}"#, Utc::now().timestamp() + 600);
+        assert!(submit(no_bypass, &format!("{},{}", all_day, snooze)).await.is_err());
+        assert!(submit(bypass, &format!(r#""w1":[],"w2":[],{}"#, snooze)).await.is_err());
+        assert!(submit(no_bypass, &format!(r#""w1":[],"w2":[],"q":1,{}"#, snooze)).await.is_err());
+    }
+
     #[tokio::test]
     async fn raid_ok() {
         tracing_subscriber::fmt::try_init().ok();
@@ -1489,4 +2096,160 @@ mod tests {
             .await
             .is_ok());
     }
+
+    #[test]
+    fn disguise() {
+        let mut input: Pokemon = serde_json::from_str(r#"{"pokestop_id":"3d716717cc65421490684ef9b213a382.16","disappear_time":1571079918,"cp":null,"form":0,"move_1":null,"longitude":11.241531239206385,"costume":0,"pokemon_id":132,"display_pokemon_id":16,"disappear_time_verified":false,"gender":1,"individual_attack":0,"spawnpoint_id":"None","latitude":43.771913285587665,"pokemon_level":15,"move_2":null,"individual_defense":15,"weight":null,"encounter_id":"12661125248363616471","height":null,"weather":1,"first_seen":1571078718,"individual_stamina":15,"last_modified_time":1571078718,"pvp_rankings_great_league":[{"rank":351,"percentage":0.9907850542156611,"level":38.5,"pokemon":17,"form":0,"cp":1490}]}"#).unwrap();
+
+        assert_eq!(super::get_disguise(&input), Some(16));
+        assert!(crate::bot::pvp::League::Great.get_best(&input).is_none());
+
+        let mut filter = [0_u8; 37];
+        filter[36] = 1;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_some());
+        filter[36] = 2;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_none());
+
+        // same species isn't a disguise
+        input.display_pokemon_id = Some(132);
+        assert_eq!(super::get_disguise(&input), None);
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_some());
+    }
+
+    #[test]
+    fn moves_and_cp() {
+        let mut input: Pokemon = serde_json::from_str(r#"{"pokestop_id":"3d716717cc65421490684ef9b213a382.16","disappear_time":1571079918,"cp":1500,"form":0,"move_1":221,"longitude":11.241531239206385,"costume":0,"pokemon_id":19,"disappear_time_verified":false,"gender":1,"individual_attack":10,"spawnpoint_id":"None","latitude":43.771913285587665,"pokemon_level":30,"move_2":281,"individual_defense":10,"weight":1.5,"encounter_id":"12661125248363616471","height":0.2,"weather":0,"first_seen":1571078718,"individual_stamina":10,"last_modified_time":1571078718}"#).unwrap();
+
+        let mut filter = [0_u8; 37];
+        // fast move 221, charged move 281 = 26 + 1 * 255
+        filter[27] = 221;
+        filter[29] = 26;
+        filter[30] = 1;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_some());
+
+        // CP between 1400 = 125 + 5 * 255 and 1600 = 70 + 6 * 255
+        filter[31] = 125;
+        filter[32] = 5;
+        filter[33] = 70;
+        filter[34] = 6;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_some());
+        input.cp = Some(1700);
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_none());
+        input.cp = None;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_none());
+
+        // every check has to pass
+        input.cp = Some(1400);
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_some());
+        input.move_2 = Some(282);
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_none());
+        input.move_2 = Some(281);
+        input.move_1 = None;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_none());
+
+        // 100% bypasses them
+        input.individual_attack = Some(15);
+        input.individual_defense = Some(15);
+        input.individual_stamina = Some(15);
+        filter[16] = 1;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_some());
+
+        // so does a youngster badge, advanced filters are skipped altogether
+        filter[16] = 0;
+        assert!(super::BotPkmn::advanced_filters(&filter, &input).unwrap().is_none());
+        filter[8] = 1;
+        assert!(super::BotPkmn::check_badge(&filter, &input));
+    }
+
+    #[test]
+    fn schedules() {
+        let time = |json: &str| serde_json::from_str::<super::BotTime>(json).unwrap();
+        let at = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        // monday 2024-03-04 and saturday 2024-03-09, Rome is UTC+1
+        let monday = at("2024-03-04T07:45:00Z");
+        let saturday = at("2024-03-09T07:45:00Z");
+
+        let legacy = time(r#"{"fi":[0,0],"fl":[0,0],"fc":0,"w1":[8],"w2":[]}"#);
+        assert_eq!(legacy.is_active(&monday), Ok(true));
+        assert_eq!(legacy.is_active(&saturday), Ok(false));
+
+        let schedule = time(
+            r#"{"fi":[0,0],"fl":[0,0],"fc":0,"s":{"d":[[[510,540]],[],[],[],[[1320,60]],[],[]],"o":[{"f":20240304,"t":20240305}]}}"#,
+        );
+        // the override turns off the whole monday
+        assert_eq!(schedule.is_active(&monday), Ok(false));
+        assert_eq!(schedule.is_active(&at("2024-03-11T07:45:00Z")), Ok(true));
+        assert_eq!(schedule.is_active(&at("2024-03-11T07:15:00Z")), Ok(false));
+        // friday night continues into saturday morning
+        assert_eq!(schedule.is_active(&at("2024-03-08T21:30:00Z")), Ok(true));
+        assert_eq!(schedule.is_active(&at("2024-03-08T23:30:00Z")), Ok(true));
+        assert_eq!(schedule.is_active(&at("2024-03-09T00:30:00Z")), Ok(false));
+        // but not into friday morning, nor sunday morning
+        assert_eq!(schedule.is_active(&at("2024-03-07T23:30:00Z")), Ok(false));
+        assert_eq!(schedule.is_active(&at("2024-03-09T23:30:00Z")), Ok(false));
+        assert_eq!(schedule.is_active(&saturday), Ok(false));
+
+        let mut snoozed = legacy.clone();
+        snoozed.z = Some(monday.timestamp() + 60);
+        assert_eq!(snoozed.is_active(&monday), Ok(false));
+        assert_eq!(snoozed.is_active(&at("2024-03-04T07:50:00Z")), Ok(true));
+        // bypasses don't apply while snoozed
+        snoozed.fi = [1, 0];
+        assert!(snoozed.has_bypass());
+        assert!(snoozed.is_snoozed(&monday));
+        assert!(!snoozed.is_snoozed(&at("2024-03-04T07:50:00Z")));
+    }
+
+    #[test]
+    fn invasion_rules() {
+        let rule = |json: &str| serde_json::from_str::<super::InvsRule>(json).unwrap();
+        let grunt = crate::lists::GruntType {
+            id: 4,
+            name: String::from("Scagnozzo"),
+            sex: Some(String::from("female")),
+            element: Some(String::from("water")),
+            lineup: vec![vec![129], vec![129, 55], vec![130]],
+            rewards: vec![1],
+        };
+
+        assert_eq!(grunt.get_rewards().collect::<Vec<_>>(), vec![129]);
+        assert!(rule(r#"{}"#).matches(&grunt));
+        assert!(rule(r#"{"e":"Water","s":"female","k":1}"#).matches(&grunt));
+        assert!(rule(r#"{"p":129}"#).matches(&grunt));
+        assert!(!rule(r#"{"p":130}"#).matches(&grunt));
+        assert!(!rule(r#"{"k":2}"#).matches(&grunt));
+        assert!(!rule(r#"{"e":"fire"}"#).matches(&grunt));
+    }
+
+    #[test]
+    fn pokemon_rules() {
+        let rule = |json: &str| serde_json::from_str::<super::PkmnRule>(json).unwrap();
+        let dratini = crate::lists::Pokemon {
+            id: 147,
+            name: String::from("Dratini"),
+            ptype: Some(String::from("Dragon")),
+            height: Some(1.8),
+            weight: Some(3.3),
+            rarity: 3,
+            scanned: 0,
+            status: 0,
+            raid: 0,
+        };
+
+        assert!(rule(r#"{"f":[1,1,90]}"#).matches(&dratini));
+        assert!(rule(r#"{"y":2,"f":[1,1,90]}"#).matches(&dratini));
+        assert!(rule(r#"{"t":"dragon","g":1,"f":[1,1,90]}"#).matches(&dratini));
+        assert!(!rule(r#"{"y":4,"f":[1,1,90]}"#).matches(&dratini));
+        assert!(!rule(r#"{"t":"Fire","f":[1,1,90]}"#).matches(&dratini));
+        assert!(!rule(r#"{"g":4,"f":[1,1,90]}"#).matches(&dratini));
+
+        assert_eq!(dratini.get_size(None, Some(3.3)), None);
+        assert_eq!(dratini.get_size(Some(0.9), None), Some(crate::lists::Size::Xxs));
+        assert_eq!(dratini.get_size(Some(1.8), None), Some(crate::lists::Size::Normal));
+        assert_eq!(dratini.get_size(Some(2.4), None), Some(crate::lists::Size::Xl));
+        assert_eq!(dratini.get_size(Some(2.8), None), Some(crate::lists::Size::Xxl));
+        // an average height with a double weight is still a big one
+        assert_eq!(dratini.get_size(Some(1.8), Some(6.6)), Some(crate::lists::Size::Xxl));
+        assert_eq!(dratini.get_size(Some(0.9), Some(2.64)), Some(crate::lists::Size::Xs));
+    }
 }
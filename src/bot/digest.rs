@@ -0,0 +1,212 @@
+use chrono::Utc;
+
+use tokio::time::{Duration, Instant};
+
+use super::message::{DigestMessage, Message};
+
+use crate::shutdown::InFlight;
+
+/// longest digest window, in minutes
+const MAX_WINDOW: u16 = 30;
+
+/// most notifications kept during quiet hours, the ones expiring first are dropped
+const MAX_AWAY: usize = 50;
+
+type Boxed = Box<dyn Message + Send + Sync>;
+
+/// Notifications of a user waiting for the end of the digest window
+#[derive(Default)]
+pub struct Digest {
+    deadline: Option<Instant>,
+    messages: Vec<Boxed>,
+    // buffered notifications are sent before exiting
+    guard: Option<InFlight>,
+}
+
+impl Digest {
+    /// Buffers a notification, the window starts with the first one
+    pub fn push(&mut self, message: Boxed, window: u16) {
+        if self.deadline.is_none() {
+            self.deadline = Some(Instant::now() + Duration::from_secs(u64::from(window.min(MAX_WINDOW)) * 60));
+            self.guard = Some(InFlight::start());
+        }
+        self.messages.push(message);
+    }
+
+    pub fn get_deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Window expired, or shutdown requested so it can't wait for the deadline
+    pub fn is_due(&self, now: Instant, stopping: bool) -> bool {
+        self.deadline.map(|deadline| stopping || deadline <= now) == Some(true)
+    }
+
+    /// Buffered notifications, grouped only when there is more than one
+    pub fn take(&mut self) -> Option<(Boxed, InFlight)> {
+        self.deadline = None;
+        let guard = self.guard.take()?;
+        let message: Boxed = if self.messages.len() == 1 {
+            self.messages.pop()?
+        } else {
+            Box::new(DigestMessage { messages: std::mem::take(&mut self.messages), away: false, skipped: 0 })
+        };
+        Some((message, guard))
+    }
+}
+
+fn is_valid(message: &Boxed, now: i64) -> bool {
+    message.get_expire().map(|expire| expire > now).unwrap_or(true)
+}
+
+/// Notifications matched during the quiet hours of a user, sent before exiting
+#[derive(Default)]
+pub struct Away {
+    messages: Vec<Boxed>,
+    // dropped over MAX_AWAY
+    skipped: usize,
+    guard: Option<InFlight>,
+}
+
+impl Away {
+    /// Buffers a notification, dropping the expired ones and the first to expire over MAX_AWAY
+    pub fn push(&mut self, message: Boxed, now: i64) {
+        self.messages.retain(|m| is_valid(m, now));
+        self.messages.push(message);
+        if self.guard.is_none() {
+            self.guard = Some(InFlight::start());
+        }
+        if self.messages.len() > MAX_AWAY {
+            let first = self.messages.iter().enumerate().min_by_key(|(_, m)| m.get_expire().unwrap_or(i64::MAX));
+            if let Some((index, _)) = first {
+                self.messages.remove(index);
+                self.skipped += 1;
+            }
+        }
+    }
+
+    /// Start of the next minute, when a new active window can begin
+    pub fn get_deadline(&self) -> Option<Instant> {
+        if self.messages.is_empty() {
+            None
+        } else {
+            let elapsed = Utc::now().timestamp().rem_euclid(60) as u64;
+            Some(Instant::now() + Duration::from_secs(60 - elapsed))
+        }
+    }
+
+    /// Summary of the notifications still valid at the given timestamp
+    pub fn take(&mut self, now: i64) -> Option<(Boxed, InFlight)> {
+        let guard = self.guard.take()?;
+        let mut messages = std::mem::take(&mut self.messages);
+        let skipped = std::mem::take(&mut self.skipped);
+        messages.retain(|m| is_valid(m, now));
+        if messages.is_empty() {
+            None
+        } else {
+            Some((Box::new(DigestMessage { messages, away: true, skipped }), guard))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use tokio::time::Instant;
+
+    use super::super::message::{LagMessage, Message};
+    use super::{Away, Digest, MAX_AWAY};
+
+    use crate::telegram::Image;
+
+    struct Expiring(i64);
+
+    #[async_trait]
+    impl Message for Expiring {
+        fn get_latitude(&self) -> f64 {
+            0_f64
+        }
+
+        fn get_longitude(&self) -> f64 {
+            0_f64
+        }
+
+        fn get_expire(&self) -> Option<i64> {
+            Some(self.0)
+        }
+
+        async fn get_caption(&self) -> Result<String, ()> {
+            Ok(String::new())
+        }
+
+        async fn _get_image(&self, _: image::DynamicImage) -> Result<Image, ()> {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn window() {
+        let mut digest = Digest::default();
+        assert!(digest.take().is_none());
+
+        digest.push(Box::new(LagMessage { lag: 1 }), 5);
+        let deadline = digest.get_deadline();
+        assert!(deadline.is_some());
+        // later notifications don't extend the window
+        digest.push(Box::new(LagMessage { lag: 2 }), 5);
+        assert_eq!(digest.get_deadline(), deadline);
+
+        assert!(!digest.is_due(Instant::now(), false));
+        assert!(digest.is_due(deadline.unwrap(), false));
+
+        assert!(digest.take().is_some());
+        assert!(digest.get_deadline().is_none());
+        assert!(digest.take().is_none());
+    }
+
+    #[test]
+    fn shutdown() {
+        let mut digest = Digest::default();
+        assert!(!digest.is_due(Instant::now(), true));
+
+        digest.push(Box::new(LagMessage { lag: 1 }), 30);
+        // flushed right away instead of waiting for the window
+        assert!(digest.is_due(Instant::now(), true));
+        assert!(digest.take().is_some());
+        // and only once
+        assert!(!digest.is_due(Instant::now(), true));
+        assert!(digest.take().is_none());
+    }
+
+    #[test]
+    fn away() {
+        let mut away = Away::default();
+        assert!(away.get_deadline().is_none());
+
+        away.push(Box::new(LagMessage { lag: 1 }), 0);
+        assert!(away.get_deadline().is_some());
+        // messages without expire are always still valid
+        assert!(away.take(0).is_some());
+        assert!(away.get_deadline().is_none());
+        assert!(away.take(0).is_none());
+    }
+
+    #[test]
+    fn away_cap() {
+        let mut away = Away::default();
+        away.push(Box::new(Expiring(10)), 0);
+        for expire in 100..(100 + MAX_AWAY as i64) {
+            away.push(Box::new(Expiring(expire)), 0);
+        }
+        // the first to expire is dropped over the cap
+        assert_eq!(away.messages.len(), MAX_AWAY);
+        assert_eq!(away.skipped, 1);
+        assert!(away.messages.iter().all(|m| m.get_expire() >= Some(100)));
+
+        // expired ones are dropped on push
+        away.push(Box::new(Expiring(1000)), 120);
+        assert_eq!(away.messages.len(), MAX_AWAY - 20);
+        assert_eq!(away.skipped, 1);
+    }
+}
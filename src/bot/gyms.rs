@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use tokio::sync::RwLock;
+
+use once_cell::sync::Lazy;
+
+use rocketmap_entities::GymDetails;
+
+/// previous and current state
+type Change = (Option<GymState>, GymState);
+
+// state of every gym seen since startup
+static GYMS: Lazy<RwLock<HashMap<String, Change>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GymState {
+    pub team: u8,
+    pub slots_available: u8,
+    pub in_battle: bool,
+}
+
+impl From<&GymDetails> for GymState {
+    fn from(gym: &GymDetails) -> Self {
+        GymState {
+            team: gym.team.get_id(),
+            slots_available: gym.slots_available,
+            in_battle: gym.in_battle == Some(true),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GymEvent {
+    TeamChanged,
+    SlotOpened,
+    UnderAttack,
+}
+
+impl GymEvent {
+    /// Id used by configs
+    pub fn get_id(&self) -> u8 {
+        match self {
+            GymEvent::TeamChanged => 1,
+            GymEvent::SlotOpened => 2,
+            GymEvent::UnderAttack => 3,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            GymEvent::TeamChanged => "cambio squadra",
+            GymEvent::SlotOpened => "posto libero",
+            GymEvent::UnderAttack => "sotto attacco",
+        }
+    }
+}
+
+pub fn get_team_name(team: u8) -> &'static str {
+    match team {
+        1 => "Saggezza",
+        2 => "Coraggio",
+        3 => "Istinto",
+        _ => "Nessuna",
+    }
+}
+
+/// Changes between two states of the same gym
+pub fn get_events(before: &GymState, after: &GymState) -> Vec<GymEvent> {
+    let mut res = Vec::new();
+    if before.team != after.team {
+        res.push(GymEvent::TeamChanged);
+    }
+    if after.slots_available > before.slots_available && after.team == before.team {
+        res.push(GymEvent::SlotOpened);
+    }
+    if after.in_battle && !before.in_battle {
+        res.push(GymEvent::UnderAttack);
+    }
+    res
+}
+
+/// Stores the current state of a gym
+pub async fn update(gym: &GymDetails) {
+    update_state(&gym.id, GymState::from(gym)).await;
+}
+
+/// Repeated webhooks consume the transition, so every change is reported once
+async fn update_state(id: &str, state: GymState) {
+    let mut lock = GYMS.write().await;
+    match lock.get_mut(id) {
+        Some(entry) => *entry = (Some(entry.1), state),
+        None => {
+            lock.insert(id.to_owned(), (None, state));
+        }
+    }
+}
+
+/// State of the gym before the given webhook, if known
+pub async fn get_previous(gym: &GymDetails) -> Option<GymState> {
+    get_previous_state(&gym.id, &GymState::from(gym)).await
+}
+
+async fn get_previous_state(id: &str, state: &GymState) -> Option<GymState> {
+    let lock = GYMS.read().await;
+    // a newer webhook may have already replaced the state, in that case the change is lost
+    lock.get(id).filter(|(_, current)| current == state).and_then(|(before, _)| *before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_events, get_previous_state, update_state, GymEvent, GymState};
+
+    #[test]
+    fn events() {
+        let state = GymState { team: 1, slots_available: 0, in_battle: false };
+
+        assert!(get_events(&state, &state).is_empty());
+        assert_eq!(get_events(&state, &GymState { team: 2, slots_available: 5, ..state }), vec![GymEvent::TeamChanged]);
+        assert_eq!(
+            get_events(&state, &GymState { slots_available: 1, in_battle: true, ..state }),
+            vec![GymEvent::SlotOpened, GymEvent::UnderAttack]
+        );
+    }
+
+    #[tokio::test]
+    async fn rescans() {
+        let state = GymState { team: 1, slots_available: 0, in_battle: false };
+        let changed = GymState { team: 2, ..state };
+        let events = |before: Option<GymState>| before.map(|b| get_events(&b, &changed)).unwrap_or_default();
+
+        update_state("rescans", state).await;
+        assert!(get_previous_state("rescans", &state).await.is_none());
+
+        update_state("rescans", changed).await;
+        assert_eq!(events(get_previous_state("rescans", &changed).await), vec![GymEvent::TeamChanged]);
+
+        // the same payload again doesn't repeat the change
+        update_state("rescans", changed).await;
+        assert!(events(get_previous_state("rescans", &changed).await).is_empty());
+    }
+}
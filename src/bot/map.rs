@@ -37,8 +37,31 @@ fn tile_xy(lat: f64, lon: f64, z: u8) -> (i64, i64) {
     (x.round() as i64, y.round() as i64)
 }
 
-pub struct Map<'a> {
-    tile_url: &'a str,
+/// pixels kept free around the points when fitting the zoom
+const OVERLAY_MARGIN: u32 = 20;
+const OVERLAY_COLOR: image::Rgba<u8> = image::Rgba([0, 120, 255, 255]);
+
+/// Size of a pixel, in meters, at the given latitude and zoom
+fn meters_per_pixel(lat: f64, z: u8, tile_width: u32) -> f64 {
+    40_075_016.686 * lat.to_radians().cos() / (num_tiles(z) as f64 * tile_width as f64)
+}
+
+/// User position and radius, in km, drawn over the map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Overlay {
+    pub lat: f64,
+    pub lon: f64,
+    pub radius: f64,
+}
+
+impl Overlay {
+    /// Identifies maps and images drawn with this overlay
+    pub fn get_key(&self) -> String {
+        format!("{:.3}_{:.3}_{:.1}", self.lat, self.lon, self.radius)
+    }
+}
+
+pub struct Map {
     tile_width: u32,
     tile_height: u32,
     zoom: u8,
@@ -46,20 +69,78 @@ pub struct Map<'a> {
     height: u32,
     lat: f64,
     lon: f64,
+    /// positions of the markers, the first one is the center unless more points are drawn
+    markers: Vec<(f64, f64)>,
+    overlay: Option<Overlay>,
 }
 
-impl<'a> Map<'a> {
-    pub fn new(tile_url: &'a str, zoom: u8, width: u32, height: u32, lat: f64, lon: f64) -> Self {
+impl Map {
+    pub fn new(zoom: u8, width: u32, height: u32, lat: f64, lon: f64) -> Self {
+        let lat = (lat * 1000.0).round() / 1000.0;
+        let lon = (lon * 1000.0).round() / 1000.0;
         Map {
-            tile_url,
             tile_width: 256,
             tile_height: 256,
             zoom,
             width,
             height,
-            lat: (lat * 1000.0).round() / 1000.0,
-            lon: (lon * 1000.0).round() / 1000.0,
+            lat,
+            lon,
+            markers: vec![(lat, lon)],
+            overlay: None,
+        }
+    }
+
+    /// Centers the map between the marker and the user position, zooming out until both are visible
+    pub fn with_overlay(mut self, overlay: Overlay) -> Self {
+        let mut points = self.markers.clone();
+        points.push((overlay.lat, overlay.lon));
+        self.fit(&points);
+        self.overlay = Some(overlay);
+        self
+    }
+
+    /// Draws a marker on every point, the map is centered and zoomed out to show all of them
+    pub fn with_markers(mut self, markers: Vec<(f64, f64)>) -> Self {
+        self.fit(&markers);
+        self.markers = markers;
+        self
+    }
+
+    /// Centers the map on the given points, with the highest zoom, up to the current one, showing them all
+    fn fit(&mut self, points: &[(f64, f64)]) {
+        if points.is_empty() {
+            return;
         }
+        let (min_lat, max_lat, min_lon, max_lon) = points.iter().fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(min_lat, max_lat, min_lon, max_lon), (lat, lon)| {
+                (min_lat.min(*lat), max_lat.max(*lat), min_lon.min(*lon), max_lon.max(*lon))
+            },
+        );
+
+        self.zoom = (1..=self.zoom)
+            .rev()
+            .find(|z| {
+                // y grows southward
+                let (ax, ay) = latlon2xy(max_lat, min_lon, *z);
+                let (bx, by) = latlon2xy(min_lat, max_lon, *z);
+                (bx - ax) * (self.tile_width as f64) <= self.width.saturating_sub(2 * OVERLAY_MARGIN) as f64
+                    && (by - ay) * (self.tile_height as f64) <= self.height.saturating_sub(2 * OVERLAY_MARGIN) as f64
+            })
+            .unwrap_or(1);
+        self.lat = (min_lat + max_lat) / 2.0;
+        self.lon = (min_lon + max_lon) / 2.0;
+    }
+
+    /// Position of a point in the composed image, given the position of the center
+    fn get_pixel(&self, lat: f64, lon: f64, center: (u32, u32)) -> (i32, i32) {
+        let (center_x, center_y) = latlon2xy(self.lat, self.lon, self.zoom);
+        let (x, y) = latlon2xy(lat, lon, self.zoom);
+        (
+            center.0 as i32 + ((x - center_x) * (self.tile_width as f64)).round() as i32,
+            center.1 as i32 + ((y - center_y) * (self.tile_height as f64)).round() as i32,
+        )
     }
 
     pub async fn get_map(&self, marker: Option<image::DynamicImage>) -> Result<image::DynamicImage, ()> {
@@ -141,10 +222,40 @@ impl<'a> Map<'a> {
             }
         }
 
+        let markers =
+            self.markers.iter().map(|(lat, lon)| self.get_pixel(*lat, *lon, (x_left, y_top))).collect::<Vec<_>>();
+        if let (Some(overlay), Some((target_x, target_y))) = (&self.overlay, markers.first()) {
+            let (user_x, user_y) = self.get_pixel(overlay.lat, overlay.lon, (x_left, y_top));
+            let radius =
+                (overlay.radius * 1000.0 / meters_per_pixel(overlay.lat, self.zoom, self.tile_width)).round() as i32;
+            imageproc::drawing::draw_hollow_circle_mut(&mut image, (user_x, user_y), radius, OVERLAY_COLOR);
+            imageproc::drawing::draw_hollow_circle_mut(&mut image, (user_x, user_y), radius - 1, OVERLAY_COLOR);
+            imageproc::drawing::draw_line_segment_mut(
+                &mut image,
+                (user_x as f32, user_y as f32),
+                (*target_x as f32, *target_y as f32),
+                OVERLAY_COLOR,
+            );
+            imageproc::drawing::draw_filled_circle_mut(
+                &mut image,
+                (user_x, user_y),
+                6,
+                image::Rgba([255, 255, 255, 255]),
+            );
+            imageproc::drawing::draw_filled_circle_mut(&mut image, (user_x, user_y), 4, OVERLAY_COLOR);
+        }
+
         if let Some(mark) = marker {
             use image::GenericImageView;
             let (width, height) = mark.dimensions();
-            image::imageops::overlay(&mut image, &mark, x_left - (width / 2), y_top - height);
+            for (x, y) in markers {
+                image::imageops::overlay(
+                    &mut image,
+                    &mark,
+                    (x.max(0) as u32).saturating_sub(width / 2),
+                    (y.max(0) as u32).saturating_sub(height),
+                );
+            }
         }
 
         // image = image.crop((
@@ -162,30 +273,30 @@ impl<'a> Map<'a> {
     }
 
     async fn get_tile(&self, x: i64, y: i64) -> Result<image::DynamicImage, ()> {
-        let tile_url = self
-            .tile_url
-            .replace("{s}", {
-                use rand::Rng;
-                match rand::thread_rng().gen_range(0..3) {
-                    0 => "a",
-                    1 => "b",
-                    _ => "c",
-                }
-            })
-            .replace("{z}", &self.zoom.to_string())
-            .replace("{x}", &x.to_string())
-            .replace("{y}", &y.to_string());
-        let url = reqwest::Url::parse(&tile_url).map_err(|e| tracing::error!("error building tile url: {}", e))?;
+        super::tiles::get_tile(self.zoom, x, y).await
+    }
+}
 
-        let res = reqwest::get(url).await.map_err(|e| tracing::error!("error retrieving tile {}: {}", tile_url, e))?;
+#[cfg(test)]
+mod tests {
+    use super::{Map, Overlay};
 
-        if !res.status().is_success() {
-            tracing::error!("tile {} retriever failed with status code {}", tile_url, res.status());
-            Err(())
-        } else {
-            let bytes = res.bytes().await.map_err(|e| tracing::error!("error reading tile {}: {}", tile_url, e))?;
+    #[test]
+    fn overlay_zoom() {
+        let near = Map::new(14, 280, 101, 45.464, 9.190).with_overlay(Overlay { lat: 45.465, lon: 9.191, radius: 1.0 });
+        assert_eq!(near.zoom, 14);
+        assert!((near.lat - 45.4645).abs() < 1e-9 && (near.lon - 9.1905).abs() < 1e-9);
 
-            image::load_from_memory(&bytes).map_err(|e| tracing::error!("error loading tile {}: {}", tile_url, e))
-        }
+        let far = Map::new(14, 280, 101, 45.464, 9.190).with_overlay(Overlay { lat: 45.504, lon: 9.190, radius: 5.0 });
+        assert!(far.zoom < 14);
+        assert_eq!(far.markers, vec![(45.464, 9.190)]);
+    }
+
+    #[test]
+    fn markers() {
+        let map =
+            Map::new(14, 400, 250, 45.464, 9.190).with_markers(vec![(45.464, 9.190), (45.500, 9.250), (45.466, 9.180)]);
+        assert!((map.lat - 45.482).abs() < 1e-9 && (map.lon - 9.215).abs() < 1e-9);
+        assert!(map.zoom < 14);
     }
 }
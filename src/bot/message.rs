@@ -1,3 +1,7 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 
 use tokio::fs::{File, OpenOptions};
@@ -17,22 +21,43 @@ use once_cell::sync::Lazy;
 
 use async_trait::async_trait;
 
-use tracing::error;
+use tracing::{debug, error};
 
-use rocketmap_entities::{DeviceTier, Gender, GymDetails, Pokemon, Pokestop, Raid, Watch};
+use rocketmap_entities::{DeviceTier, Gender, GymDetails, Pokemon, Pokestop, PvpRanking, Raid, Watch};
 
-use super::{file_cache::FileCache, BotConfigs};
+use super::{
+    config::get_disguise,
+    file_cache::FileCache,
+    gyms::{get_team_name, GymEvent, GymState},
+    map::{Map, Overlay},
+    pvp,
+    reminders::{self, RaidReminder, ReminderKind},
+    sent,
+    template::{self, Template},
+    theme, BotConfigs,
+};
 
-use crate::config::CONFIG;
+use crate::config::{ImageFormat, CONFIG};
 use crate::db::MYSQL;
-use crate::lists::{FORMS, GRUNTS, LIST, MOVES};
-use crate::telegram::{send_message, send_photo, CallResult, Image};
+use crate::lists::{self, FORMS, GRUNTS, LIST, MOVES};
+use crate::telegram::{
+    edit_message_caption, edit_message_media, get_message_id, send_message, send_photo, CallResult, Image,
+};
 
 static MAP_CACHE: Lazy<FileCache<PathBuf, Result<image::DynamicImage, ()>>> =
-    Lazy::new(|| FileCache::new(CONFIG.service.lru_size));
-static IMG_CACHE: Lazy<FileCache<PathBuf, Result<Image, ()>>> = Lazy::new(|| FileCache::new(CONFIG.service.lru_size));
+    Lazy::new(|| FileCache::new(CONFIG.load().service.lru_size));
+/// digest entries listed in the caption, which has a limited length
+const MAX_DIGEST_LINES: usize = 15;
 
-fn truncate_str(s: &str, limit: usize, placeholder: char) -> String {
+/// JPEG quality when not configured
+const DEFAULT_QUALITY: u8 = 85;
+/// JPEG quality isn't lowered further to meet the size budget
+const MIN_QUALITY: u8 = 40;
+
+static IMG_CACHE: Lazy<FileCache<PathBuf, Result<Image, ()>>> =
+    Lazy::new(|| FileCache::new(CONFIG.load().service.lru_size));
+
+pub fn truncate_str(s: &str, limit: usize, placeholder: char) -> String {
     if s.is_empty() {
         return placeholder.to_string();
     }
@@ -47,14 +72,14 @@ fn truncate_str(s: &str, limit: usize, placeholder: char) -> String {
     chars.into_iter().collect()
 }
 
-async fn open_font(path: &str) -> Result<rusttype::Font<'static>, ()> {
+pub async fn open_font(path: &str) -> Result<rusttype::Font<'static>, ()> {
     let mut file = File::open(path).await.map_err(|e| error!("error opening font {}: {}", path, e))?;
     let mut data = Vec::new();
     file.read_to_end(&mut data).await.map_err(|e| error!("error reading font {}: {}", path, e))?;
     rusttype::Font::try_from_vec(data).ok_or_else(|| error!("error decoding font {}", path))
 }
 
-async fn open_image(path: &Path) -> Result<image::DynamicImage, ()> {
+pub async fn open_image(path: &Path) -> Result<image::DynamicImage, ()> {
     let mut file = File::open(path).await.map_err(|e| error!("error opening image {}: {}", path.display(), e))?;
     let mut data = Vec::new();
     file.read_to_end(&mut data).await.map_err(|e| error!("error reading image {}: {}", path.display(), e))?;
@@ -66,7 +91,50 @@ async fn save_image(img: &image::DynamicImage, path: &Path) -> Result<Vec<u8>, (
     let mut out = Vec::new();
     img.write_to(&mut out, image::ImageOutputFormat::Png)
         .map_err(|e| error!("error converting image {}: {}", path.display(), e))?;
+    write_file(&out, path).await?;
+    Ok(out)
+}
+
+/// Saves a notification image in the configured format, lowering JPEG quality until it fits the size budget
+async fn save_sent_image(img: &image::DynamicImage, path: &Path) -> Result<Vec<u8>, ()> {
+    let config = CONFIG.load_full();
+    let out = match config.images.format {
+        ImageFormat::Png => {
+            let mut out = Vec::new();
+            img.write_to(&mut out, image::ImageOutputFormat::Png)
+                .map_err(|e| error!("error converting image {}: {}", path.display(), e))?;
+            out
+        }
+        ImageFormat::Jpeg => {
+            // JPEG has no transparency, flatten over white
+            let (width, height) = image::GenericImageView::dimensions(img);
+            let mut flat = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 255, 255, 255]));
+            image::imageops::overlay(&mut flat, img, 0, 0);
+            let flat = image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(flat).to_rgb8());
+
+            let mut quality = config.images.quality.unwrap_or(DEFAULT_QUALITY).clamp(MIN_QUALITY, 100);
+            loop {
+                let mut out = Vec::new();
+                flat.write_to(&mut out, image::ImageOutputFormat::Jpeg(quality))
+                    .map_err(|e| error!("error converting image {}: {}", path.display(), e))?;
+                match config.images.max_size {
+                    Some(max) if out.len() > max && quality > MIN_QUALITY => {
+                        quality = quality.saturating_sub(10).max(MIN_QUALITY);
+                    }
+                    _ => break out,
+                }
+            }
+        }
+    };
+    if let Some(max) = config.images.max_size.filter(|max| out.len() > *max) {
+        debug!("image {} is {} bytes, over the budget of {}", path.display(), out.len(), max);
+    }
 
+    write_file(&out, path).await?;
+    Ok(out)
+}
+
+async fn write_file(out: &[u8], path: &Path) -> Result<(), ()> {
     let mut file = OpenOptions::new()
         .write(true)
         .truncate(true)
@@ -74,17 +142,69 @@ async fn save_image(img: &image::DynamicImage, path: &Path) -> Result<Vec<u8>, (
         .open(path)
         .await
         .map_err(|e| error!("error saving image {}: {}", path.display(), e))?;
-    file.write_all(&out).await.map_err(|e| error!("error writing image {}: {}", path.display(), e))?;
+    file.write_all(out).await.map_err(|e| error!("error writing image {}: {}", path.display(), e))?;
 
-    Ok(out)
+    Ok(())
 }
 
-fn get_text_width(font: &rusttype::Font, scale: rusttype::Scale, text: &str) -> i32 {
+pub fn get_text_width(font: &rusttype::Font, scale: rusttype::Scale, text: &str) -> i32 {
     let space = font.glyph(' ').scaled(scale).h_metrics().advance_width.round() as i32;
     font.layout(text, scale, rusttype::Point { x: 0f32, y: 0f32 })
         .fold(0, |acc, l| acc + l.pixel_bounding_box().map(|bb| bb.width()).unwrap_or_else(|| space))
 }
 
+fn format_time(timestamp: i64) -> Option<String> {
+    Some(Utc.timestamp_opt(timestamp, 0).single()?.with_timezone(&Rome).format("%T").to_string())
+}
+
+/// Tells apart maps and images drawn for different users
+fn get_overlay_suffix(overlay: Option<&Overlay>) -> String {
+    overlay.map(|o| format!("_{}", o.get_key())).unwrap_or_default()
+}
+
+/// Renders an image with a template, sharing the cache with builtin layouts
+async fn get_template_image(
+    kind: &'static str,
+    template: Template,
+    values: template::Values,
+    map: image::DynamicImage,
+) -> Result<Image, ()> {
+    let img_path_str = format!(
+        "{}img_sent/{}_{}_{:x}.{}",
+        CONFIG.load().images.bot,
+        kind,
+        Utc::now().with_timezone(&Rome).format("%Y%m%d%H"),
+        template.get_hash(&values),
+        CONFIG.load().images.format.get_extension()
+    );
+
+    IMG_CACHE
+        .get(img_path_str.into(), |img_path| async move {
+            if !img_path.exists() {
+                let image = template.render(&values, &map).await?;
+                let bytes = save_sent_image(&image, &img_path).await?;
+                if CONFIG.load().images.bot_pub.is_none() {
+                    return Ok(Image::Bytes(bytes));
+                }
+            }
+
+            if let Some(url) = &CONFIG.load().images.bot_pub {
+                Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)))
+            } else {
+                let mut image = File::open(&img_path)
+                    .await
+                    .map_err(|e| error!("error opening {} image {}: {}", kind, img_path.display(), e))?;
+                let mut bytes = Vec::new();
+                image
+                    .read_to_end(&mut bytes)
+                    .await
+                    .map_err(|e| error!("error reading {} image {}: {}", kind, img_path.display(), e))?;
+                Ok(Image::Bytes(bytes))
+            }
+        })
+        .await
+}
+
 fn meteo_icon(meteo: u8) -> Result<String, ()> {
     Ok(format!(
         " {}",
@@ -102,6 +222,24 @@ fn meteo_icon(meteo: u8) -> Result<String, ()> {
     ))
 }
 
+/// Pokémon types boosted by every weather condition
+fn boosted_types(meteo: u8) -> &'static [&'static str] {
+    match meteo {
+        1 => &["grass", "ground", "fire"],     //CLEAR
+        2 => &["water", "electric", "bug"],    //RAINY
+        3 => &["normal", "rock"],              //PARTLY_CLOUDY
+        4 => &["fairy", "fighting", "poison"], //OVERCAST
+        5 => &["dragon", "flying", "psychic"], //WINDY
+        6 => &["ice", "steel"],                //SNOW
+        7 => &["dark", "ghost"],               //FOG
+        _ => &[],
+    }
+}
+
+fn is_boosted(pokemon: &lists::Pokemon, meteo: u8) -> bool {
+    boosted_types(meteo).iter().any(|t| pokemon.has_type(t))
+}
+
 fn get_mega_desc(evo: &Option<u8>) -> &str {
     match evo {
         Some(1) => "(Mega)",
@@ -111,15 +249,65 @@ fn get_mega_desc(evo: &Option<u8>) -> &str {
     }
 }
 
+/// Replaces a sent message, falling back to a new one when it can't be edited
+/// calls are lazy, so the later ones are made only if needed
+async fn edit_or_send<M, C, S>(edit_media: M, edit_caption: C, send: S) -> Result<String, CallResult>
+where
+    M: Future<Output = Result<String, CallResult>>,
+    C: Future<Output = Result<String, CallResult>>,
+    S: Future<Output = Result<String, CallResult>>,
+{
+    match edit_media.await {
+        Ok(body) => Ok(body),
+        // the image could be unchanged, try with the caption alone
+        Err(_) => match edit_caption.await {
+            Ok(body) => Ok(body),
+            // the original message could have been deleted, send a new one
+            Err(_) => send.await,
+        },
+    }
+}
+
 #[async_trait]
 pub trait Message {
     async fn send(&self, chat_id: &str, image: Image, map_type: &str) -> Result<(), ()> {
-        let caption = self.get_caption().await?;
-        let temp = send_photo(&CONFIG.telegram.bot_token, chat_id, image)
-            .set_caption(&caption)
-            .set_reply_markup(self.message_button(chat_id, map_type)?);
-        match temp.send().await {
-            Ok(_) => {
+        let caption = match theme::get_footer() {
+            Some(footer) => format!("{}\n\n{}", self.get_caption().await?, footer),
+            None => self.get_caption().await?,
+        };
+        let config = CONFIG.load_full();
+        let reply_markup = self.message_button(chat_id, map_type)?;
+        let res = match self.replaces(chat_id).await {
+            Some(message_id) => {
+                let bot_token = &config.telegram.bot_token;
+                edit_or_send(
+                    edit_message_media(bot_token, chat_id, message_id, image.clone())
+                        .set_caption(&caption)
+                        .set_reply_markup(reply_markup.clone())
+                        .send(),
+                    edit_message_caption(bot_token, chat_id, message_id, &caption)
+                        .set_reply_markup(reply_markup.clone())
+                        .send(),
+                    send_photo(bot_token, chat_id, image).set_caption(&caption).set_reply_markup(reply_markup).send(),
+                )
+                .await
+            }
+            None => {
+                send_photo(&config.telegram.bot_token, chat_id, image)
+                    .set_caption(&caption)
+                    .set_reply_markup(reply_markup)
+                    .send()
+                    .await
+            }
+        };
+        match res {
+            Ok(body) => {
+                let message_id = get_message_id(&body);
+                if let (Some(message_id), Some(expire)) = (message_id, self.get_expire()) {
+                    sent::add_expire(chat_id, message_id, expire).await.ok();
+                }
+                self.after_send(chat_id, message_id).await.ok();
+
                 let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
                 self.update_stats(&mut conn).await?;
 
@@ -159,8 +347,14 @@ pub trait Message {
         // $lat = number_format(round($ilat, 3), 3);
         // $lon = number_format(round($ilon, 3), 3);
         // $map_path = "../../data/bot/img_maps/" . $lat . "_" . $lon . ".png";
-        let map_path_str =
-            format!("{}img_maps/{:.3}_{:.3}.png", CONFIG.images.bot, self.get_latitude(), self.get_longitude());
+        let overlay = self.get_overlay().copied();
+        let map_path_str = format!(
+            "{}img_maps/{:.3}_{:.3}{}.png",
+            CONFIG.load().images.bot,
+            self.get_latitude(),
+            self.get_longitude(),
+            get_overlay_suffix(overlay.as_ref())
+        );
 
         MAP_CACHE
             .get(map_path_str.into(), |map_path| async move {
@@ -168,12 +362,18 @@ pub trait Message {
                     return open_image(&map_path).await;
                 }
 
-                let map =
-                    super::map::Map::new(&CONFIG.osm.tile_url, 14, 280, 101, self.get_latitude(), self.get_longitude());
-                let marker: PathBuf = format!("{}img/marker.png", CONFIG.images.assets).into();
+                let config = CONFIG.load_full();
+                let mut map = Map::new(14, 280, 101, self.get_latitude(), self.get_longitude());
+                if let Some(overlay) = overlay {
+                    map = map.with_overlay(overlay);
+                }
+                let marker: PathBuf = format!("{}img/marker.png", config.images.assets).into();
                 let image = map.get_map(open_image(&marker).await.ok()).await?;
 
-                save_image(&image, &map_path).await?;
+                // maps with an overlay belong to a single user, they aren't worth keeping
+                if overlay.is_none() {
+                    save_image(&image, &map_path).await?;
+                }
 
                 Ok(image)
             })
@@ -182,6 +382,14 @@ pub trait Message {
 
     async fn get_image(&self) -> Result<Image, ()> {
         let map = self.get_map().await?;
+        if let Some((kind, mut values)) = self.get_template_values() {
+            if let Some(template) = Template::load(kind).await {
+                if let Some(overlay) = self.get_overlay() {
+                    values.insert("overlay", overlay.get_key());
+                }
+                return get_template_image(kind, template, values, map).await;
+            }
+        }
         self._get_image(map).await
     }
 
@@ -219,9 +427,43 @@ pub trait Message {
 
     async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()>;
 
+    /// User position drawn on the map
+    fn get_overlay(&self) -> Option<&Overlay> {
+        None
+    }
+
+    /// Short description used when grouped in a digest, None when the message must be sent alone
+    fn get_digest_line(&self) -> Option<String> {
+        None
+    }
+
+    /// Frequent notifications, buffered during the digest window of the user
+    fn is_frequent(&self) -> bool {
+        false
+    }
+
+    /// Kind and values used when a template replaces the builtin image layout
+    fn get_template_values(&self) -> Option<(&'static str, template::Values)> {
+        None
+    }
+
     async fn update_stats(&self, _: &mut Conn) -> Result<(), ()> {
         Ok(())
     }
+
+    /// Timestamp after which the notification is useless and can be deleted
+    fn get_expire(&self) -> Option<i64> {
+        None
+    }
+
+    /// Id of a previously sent message to be edited instead of sending a new one
+    async fn replaces(&self, _chat_id: &str) -> Option<i64> {
+        None
+    }
+
+    async fn after_send(&self, _chat_id: &str, _message_id: Option<i64>) -> Result<(), ()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -231,6 +473,26 @@ pub struct PokemonMessage {
     pub distance: f64,
     pub direction: String,
     pub debug: Option<String>,
+    pub overlay: Option<Overlay>,
+}
+
+impl PokemonMessage {
+    /// Best ranked evolution for every league, if good enough to be shown
+    fn get_pvp_ranks(&self) -> Vec<(pvp::League, PvpRanking)> {
+        pvp::LEAGUES
+            .iter()
+            .filter_map(|league| league.get_best(&self.pokemon).map(|rank| (*league, rank)))
+            .filter(|(_, rank)| rank.rank.map(|r| r <= pvp::SHOWN_RANK) == Some(true))
+            .collect()
+    }
+
+    /// Size class, only when different from the normal one
+    fn get_size(&self) -> Option<lists::Size> {
+        LIST.load()
+            .get(&self.pokemon.pokemon_id)
+            .and_then(|p| p.get_size(self.pokemon.height, self.pokemon.weight))
+            .filter(|size| *size != lists::Size::Normal)
+    }
 }
 
 #[async_trait]
@@ -243,6 +505,87 @@ impl Message for PokemonMessage {
         self.pokemon.longitude
     }
 
+    fn get_overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    fn get_expire(&self) -> Option<i64> {
+        Some(self.pokemon.disappear_time)
+    }
+
+    fn is_frequent(&self) -> bool {
+        true
+    }
+
+    fn get_digest_line(&self) -> Option<String> {
+        let name = LIST
+            .load()
+            .get(&self.pokemon.pokemon_id)
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| format!("#{}", self.pokemon.pokemon_id));
+        Some(format!(
+            "{}{}{} fino alle {} ({:.1} km)",
+            name,
+            self.iv.map(|iv| format!(" {}%", iv)).unwrap_or_default(),
+            self.pokemon.pokemon_level.map(|l| format!(" L{}", l)).unwrap_or_default(),
+            format_time(self.pokemon.disappear_time)?,
+            self.distance
+        ))
+    }
+
+    fn get_template_values(&self) -> Option<(&'static str, template::Values)> {
+        let list = LIST.load();
+        let moves = MOVES.load();
+        let mut values = template::Values::new();
+        values.insert("pokemon_id", self.pokemon.pokemon_id.to_string());
+        values.insert("name", list.get(&self.pokemon.pokemon_id).map(|p| p.name.to_uppercase()).unwrap_or_default());
+        values.insert("gender", self.pokemon.gender.get_glyph());
+        values.insert("expire", format_time(self.pokemon.disappear_time)?);
+        if let Some(form) = self.pokemon.form.filter(|f| *f > 0) {
+            values.insert("form", form.to_string());
+            if let Some(f) = FORMS.load().get(&form).filter(|f| !f.hidden) {
+                values.insert("form_name", f.name.clone());
+            }
+        }
+        if let Some(iv) = self.iv {
+            values.insert("iv", iv.to_string());
+            values.insert(
+                "iv_class",
+                String::from(match iv {
+                    0..=79 => "norm",
+                    80..=89 => "med",
+                    90..=99 => "hi",
+                    _ => "top",
+                }),
+            );
+        }
+        for (key, value) in [
+            ("cp", self.pokemon.cp.map(|cp| cp.to_string())),
+            ("level", self.pokemon.pokemon_level.map(|l| l.to_string())),
+            ("atk", self.pokemon.individual_attack.map(|i| i.to_string())),
+            ("def", self.pokemon.individual_defense.map(|i| i.to_string())),
+            ("sta", self.pokemon.individual_stamina.map(|i| i.to_string())),
+            ("move_1", self.pokemon.move_1.and_then(|id| moves.get(&id)).map(|m| m.to_uppercase())),
+            ("move_2", self.pokemon.move_2.and_then(|id| moves.get(&id)).map(|m| m.to_uppercase())),
+            ("weather", self.pokemon.weather.filter(|w| *w > 0).map(|w| w.to_string())),
+            ("size", self.get_size().map(|s| s.get_name().to_owned())),
+            ("disguise", get_disguise(&self.pokemon).and_then(|id| list.get(&id)).map(|p| p.name.to_uppercase())),
+        ] {
+            if let Some(value) = value {
+                values.insert(key, value);
+            }
+        }
+        values.insert(
+            "pvp",
+            self.get_pvp_ranks()
+                .into_iter()
+                .map(|(league, rank)| format!("{} #{}", league.get_short_name(), rank.rank.unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join("  "),
+        );
+        Some(("pokemon", values))
+    }
+
     async fn get_caption(&self) -> Result<String, ()> {
         // $icon_pkmn = "\xf0\x9f\x94\xb0 #" . $t_msg["pokemon_id"];
         // $icon_raid = "\xe2\x9a\x94\xef\xb8\x8f";
@@ -250,23 +593,14 @@ impl Message for PokemonMessage {
         //   $icon_pkmn = "\xf0\x9f\x8e\x81"; // natale
         //   $icon_raid = "\xf0\x9f\x8e\x84"; // natale
         // }
-        let date = Utc::now();
-        let date: usize = date
-            .with_timezone(&Rome)
-            .format("%m%d")
-            .to_string()
-            .parse()
-            .map_err(|e| error!("error parsing date: {}", e))?;
-        let icon = if !(106..=1222).contains(&date) {
-            String::from_utf8(vec![0xf0, 0x9f, 0x8e, 0x81]) //natale
-                .map_err(|e| error!("error parsing pokemon christmas icon: {}", e))?
-        } else {
-            format!(
+        let icon = match theme::get_icon("pokemon") {
+            Some(icon) => icon,
+            None => format!(
                 "{} #{}",
                 String::from_utf8(vec![0xf0, 0x9f, 0x94, 0xb0])
                     .map_err(|e| error!("error parsing pokemon icon: {}", e))?,
                 self.pokemon.pokemon_id
-            )
+            ),
         };
 
         // $dir_icon = " " . $t_msg["direction"];
@@ -304,7 +638,7 @@ impl Message for PokemonMessage {
                         None => None,
                     }
                     .unwrap_or_default(),
-                    match self.pokemon.display_pokemon_id {
+                    match get_disguise(&self.pokemon) {
                         Some(id) => LIST.load().get(&id).map(|f| format!(" ({})", f.name)),
                         None => None,
                     }
@@ -339,7 +673,7 @@ impl Message for PokemonMessage {
                         None => None,
                     }
                     .unwrap_or_default(),
-                    match self.pokemon.display_pokemon_id {
+                    match get_disguise(&self.pokemon) {
                         Some(id) => LIST.load().get(&id).map(|f| format!(" ({})", f.name)),
                         None => None,
                     }
@@ -352,6 +686,31 @@ impl Message for PokemonMessage {
                 .replace(&gender.repeat(2), &gender) //fix nidoran double gender
         };
 
+        let mut caption = caption;
+        for (league, rank) in self.get_pvp_ranks() {
+            write!(
+                caption,
+                "\n{} {}: #{} {}{}",
+                String::from_utf8(vec![0xf0, 0x9f, 0x8f, 0x86])
+                    .map_err(|e| error!("error encoding pvp icon: {}", e))?,
+                league.get_name(),
+                rank.rank.unwrap_or_default(),
+                LIST.load().get(&rank.pokemon).map(|p| p.name.to_uppercase()).unwrap_or_default(),
+                rank.percentage.map(|p| format!(" ({:.1}%)", p * 100_f64)).unwrap_or_default(),
+            )
+            .map_err(|_| ())?;
+        }
+        if let Some(size) = self.get_size() {
+            write!(
+                caption,
+                "\n{} Taglia {}",
+                String::from_utf8(vec![0xf0, 0x9f, 0x93, 0x8f])
+                    .map_err(|e| error!("error encoding size icon: {}", e))?,
+                size.get_name()
+            )
+            .map_err(|_| ())?;
+        }
+
         Ok(match self.debug {
             Some(ref s) => format!("{}\n\n{}", caption, s),
             None => caption,
@@ -361,19 +720,25 @@ impl Message for PokemonMessage {
     async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
         let timestamp = Utc.timestamp_opt(self.pokemon.disappear_time, 0).single().ok_or(())?;
         let img_path_str = format!(
-            "{}img_sent/poke_{}_{}_{}_{}.png",
-            CONFIG.images.bot,
+            "{}img_sent/poke_{}_{}_{}_{}{}.{}",
+            CONFIG.load().images.bot,
             timestamp.with_timezone(&Rome).format("%Y%m%d%H"),
             self.pokemon.encounter_id,
             self.pokemon.pokemon_id,
-            self.iv.map(|iv| format!("{:.0}", iv)).unwrap_or_default()
+            self.iv.map(|iv| format!("{:.0}", iv)).unwrap_or_default(),
+            get_overlay_suffix(self.get_overlay()),
+            CONFIG.load().images.format.get_extension()
         );
 
         IMG_CACHE
             .get(img_path_str.into(), |img_path| async move {
                 if img_path.exists() {
-                    if let Some(url) = &CONFIG.images.bot_pub {
-                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)));
+                    if let Some(url) = &CONFIG.load().images.bot_pub {
+                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(
+                            &CONFIG.load().images.bot,
+                            url,
+                            1,
+                        )));
                     } else {
                         let mut image = File::open(&img_path)
                             .await
@@ -388,11 +753,11 @@ impl Message for PokemonMessage {
                 }
 
                 let f_cal1 = {
-                    let font = format!("{}fonts/calibri.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibri.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 let f_cal2 = {
-                    let font = format!("{}fonts/calibrib.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibrib.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 let scale11 = rusttype::Scale::uniform(16f32);
@@ -402,18 +767,13 @@ impl Message for PokemonMessage {
 
                 // $mBg = null;
                 let mut background = {
-                    let path: PathBuf = format!(
-                        "{}{}",
-                        CONFIG.images.sender,
-                        match self.iv {
-                            Some(i) if i < 80 => "images/msg-bgs/msg-poke-big-norm.png",
-                            Some(i) if (80..90).contains(&i) => "images/msg-bgs/msg-poke-big-med.png",
-                            Some(i) if (90..100).contains(&i) => "images/msg-bgs/msg-poke-big-hi.png",
-                            Some(i) if i >= 100 => "images/msg-bgs/msg-poke-big-top.png",
-                            _ => "images/msg-bgs/msg-poke-sm.png",
-                        }
-                    )
-                    .into();
+                    let path = theme::get_background(match self.iv {
+                        Some(i) if i < 80 => "msg-poke-big-norm.png",
+                        Some(i) if (80..90).contains(&i) => "msg-poke-big-med.png",
+                        Some(i) if (90..100).contains(&i) => "msg-poke-big-hi.png",
+                        Some(i) if i >= 100 => "msg-poke-big-top.png",
+                        _ => "msg-poke-sm.png",
+                    });
                     open_image(&path).await?
                 };
 
@@ -421,7 +781,9 @@ impl Message for PokemonMessage {
                     Some(form) if form > 0 => {
                         let image: PathBuf = format!(
                             "{}img/pkmns/shuffle/{}-{}.png",
-                            CONFIG.images.assets, self.pokemon.pokemon_id, form
+                            CONFIG.load().images.assets,
+                            self.pokemon.pokemon_id,
+                            form
                         )
                         .into();
                         match open_image(&image).await {
@@ -429,7 +791,8 @@ impl Message for PokemonMessage {
                             Err(_) => {
                                 let image: PathBuf = format!(
                                     "{}img/pkmns/shuffle/{}.png",
-                                    CONFIG.images.assets, self.pokemon.pokemon_id
+                                    CONFIG.load().images.assets,
+                                    self.pokemon.pokemon_id
                                 )
                                 .into();
                                 open_image(&image).await?
@@ -438,7 +801,8 @@ impl Message for PokemonMessage {
                     }
                     _ => {
                         let image: PathBuf =
-                            format!("{}img/pkmns/shuffle/{}.png", CONFIG.images.assets, self.pokemon.pokemon_id).into();
+                            format!("{}img/pkmns/shuffle/{}.png", CONFIG.load().images.assets, self.pokemon.pokemon_id)
+                                .into();
                         open_image(&image).await?
                     }
                 };
@@ -449,7 +813,7 @@ impl Message for PokemonMessage {
                     Gender::Male | Gender::Female => {
                         let path: PathBuf = format!(
                             "{}img/{}.png",
-                            CONFIG.images.assets,
+                            CONFIG.load().images.assets,
                             if self.pokemon.gender == Gender::Female { "female" } else { "male" }
                         )
                         .into();
@@ -479,21 +843,43 @@ impl Message for PokemonMessage {
                     &name,
                 );
 
-                if let Some(id) = self.pokemon.form {
-                    if let Some(form_name) =
-                        FORMS.load().get(&id).and_then(|f| if f.hidden { None } else { Some(&f.name) })
-                    {
-                        let dm = get_text_width(&f_cal2, scale18, &name);
-                        imageproc::drawing::draw_text_mut(
-                            &mut background,
-                            image::Rgba::<u8>([0, 0, 0, 0]),
-                            73 + dm as u32,
-                            7,
-                            scale11,
-                            &f_cal2,
-                            &format!("({})", form_name),
-                        );
-                    }
+                // form and species shown in game by disguised spawns, next to the true species name
+                let labels = self
+                    .pokemon
+                    .form
+                    .and_then(|id| {
+                        FORMS.load().get(&id).and_then(|f| if f.hidden { None } else { Some(f.name.clone()) })
+                    })
+                    .into_iter()
+                    .chain(get_disguise(&self.pokemon).map(|id| {
+                        format!("come {}", LIST.load().get(&id).map(|p| p.name.to_uppercase()).unwrap_or_default())
+                    }))
+                    .map(|label| format!("({})", label))
+                    .collect::<Vec<_>>();
+                if !labels.is_empty() {
+                    let dm = get_text_width(&f_cal2, scale18, &name);
+                    imageproc::drawing::draw_text_mut(
+                        &mut background,
+                        image::Rgba::<u8>([0, 0, 0, 0]),
+                        73 + dm as u32,
+                        7,
+                        scale11,
+                        &f_cal2,
+                        &labels.join(" "),
+                    );
+                }
+
+                if let Some(size) = self.get_size() {
+                    let dm = get_text_width(&f_cal2, scale11, size.get_name());
+                    imageproc::drawing::draw_text_mut(
+                        &mut background,
+                        image::Rgba::<u8>([0, 0, 0, 0]),
+                        (275 - dm) as u32,
+                        7,
+                        scale11,
+                        &f_cal2,
+                        size.get_name(),
+                    );
                 }
 
                 // imagettftext($mBg, 12, 0, 82, 46, 0x00000000, $f_cal2, $v_exit);
@@ -508,6 +894,26 @@ impl Message for PokemonMessage {
                     &v_exit.with_timezone(&Rome).format("%T").to_string(),
                 );
 
+                // best pvp ranks, right aligned without overlapping the despawn time
+                let ranks = self
+                    .get_pvp_ranks()
+                    .into_iter()
+                    .map(|(league, rank)| format!("{} #{}", league.get_short_name(), rank.rank.unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                if !ranks.is_empty() {
+                    let dm = get_text_width(&f_cal2, scale11, &ranks);
+                    imageproc::drawing::draw_text_mut(
+                        &mut background,
+                        image::Rgba::<u8>([0, 0, 0, 0]),
+                        (275 - dm).max(150) as u32,
+                        35,
+                        scale11,
+                        &f_cal2,
+                        &ranks,
+                    );
+                }
+
                 //     imagecopymerge($mBg, $mMap, 0, ($v_ivs ? 136 : 58), 0, 0, 280, 101, 100);
                 image::imageops::overlay(&mut background, &map, 0, if self.iv.is_some() { 136 } else { 58 });
 
@@ -611,10 +1017,10 @@ impl Message for PokemonMessage {
                     );
                 }
 
-                let bytes = save_image(&background, &img_path).await?;
+                let bytes = save_sent_image(&background, &img_path).await?;
 
-                if let Some(url) = &CONFIG.images.bot_pub {
-                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)))
+                if let Some(url) = &CONFIG.load().images.bot_pub {
+                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)))
                 } else {
                     Ok(Image::Bytes(bytes))
                 }
@@ -681,6 +1087,8 @@ pub struct RaidMessage {
     pub raid: Raid,
     pub distance: f64,
     pub debug: Option<String>,
+    pub reminders: Option<[u8; 3]>,
+    pub overlay: Option<Overlay>,
 }
 
 #[async_trait]
@@ -693,6 +1101,56 @@ impl Message for RaidMessage {
         self.raid.longitude
     }
 
+    fn get_overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    fn get_expire(&self) -> Option<i64> {
+        Some(self.raid.end)
+    }
+
+    fn get_digest_line(&self) -> Option<String> {
+        let boss = match self.raid.pokemon_id.filter(|id| *id > 0) {
+            Some(id) => LIST.load().get(&id).map(|p| p.name.clone()).unwrap_or_else(|| format!("#{}", id)),
+            None => String::from("Uovo"),
+        };
+        Some(format!(
+            "Raid L{} {} a {} fino alle {} ({:.1} km)",
+            self.raid.level,
+            boss,
+            self.raid.gym_name,
+            format_time(self.raid.end)?,
+            self.distance
+        ))
+    }
+
+    fn get_template_values(&self) -> Option<(&'static str, template::Values)> {
+        let moves = MOVES.load();
+        let mut values = template::Values::new();
+        values.insert("gym_name", self.raid.gym_name.clone());
+        values.insert("level", self.raid.level.to_string());
+        values.insert("team", self.raid.team_id.get_id().to_string());
+        values.insert("ex", u8::from(self.raid.ex_raid_eligible == Some(true)).to_string());
+        values.insert("start", format_time(self.raid.start)?);
+        values.insert("end", format_time(self.raid.end)?);
+        if let Some(pokemon_id) = self.raid.pokemon_id.filter(|id| *id > 0) {
+            values.insert("pokemon_id", pokemon_id.to_string());
+            values.insert("name", LIST.load().get(&pokemon_id).map(|p| p.name.to_uppercase()).unwrap_or_default());
+            values.insert("mega", get_mega_desc(&self.raid.evolution).to_owned());
+        }
+        for (key, value) in [
+            ("form", self.raid.form.filter(|f| *f > 0).map(|f| f.to_string())),
+            ("cp", self.raid.cp.map(|cp| cp.to_string())),
+            ("move_1", self.raid.move_1.and_then(|id| moves.get(&id)).map(|m| m.to_uppercase())),
+            ("move_2", self.raid.move_2.and_then(|id| moves.get(&id)).map(|m| m.to_uppercase())),
+        ] {
+            if let Some(value) = value {
+                values.insert(key, value);
+            }
+        }
+        Some(("raid", values))
+    }
+
     async fn get_caption(&self) -> Result<String, ()> {
         // $icon_pkmn = "\xf0\x9f\x94\xb0 #" . $t_msg["pokemon_id"];
         // $icon_raid = "\xe2\x9a\x94\xef\xb8\x8f";
@@ -700,19 +1158,10 @@ impl Message for RaidMessage {
         //   $icon_pkmn = "\xf0\x9f\x8e\x81"; // natale
         //   $icon_raid = "\xf0\x9f\x8e\x84"; // natale
         // }
-        let date = Utc::now();
-        let date: usize = date
-            .with_timezone(&Rome)
-            .format("%m%d")
-            .to_string()
-            .parse()
-            .map_err(|e| error!("error parsing date: {}", e))?;
-        let icon = if !(106..=1222).contains(&date) {
-            String::from_utf8(vec![0xf0, 0x9f, 0x8e, 0x84]) //natale
-                .map_err(|e| error!("error parsing raid christmas icon: {}", e))?
-        } else {
-            String::from_utf8(vec![0xe2, 0x9a, 0x94, 0xef, 0xb8, 0x8f])
-                .map_err(|e| error!("error parsing raid icon: {}", e))?
+        let icon = match theme::get_icon("raid") {
+            Some(icon) => icon,
+            None => String::from_utf8(vec![0xe2, 0x9a, 0x94, 0xef, 0xb8, 0x8f])
+                .map_err(|e| error!("error parsing raid icon: {}", e))?,
         };
 
         let caption = if let Some(pokemon_id) = self.raid.pokemon_id.and_then(|id| if id > 0 { Some(id) } else { None })
@@ -784,19 +1233,25 @@ impl Message for RaidMessage {
     async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
         let now = Utc::now();
         let img_path_str = format!(
-            "{}img_sent/raid_{}_{}_{}_{}.png",
-            CONFIG.images.bot,
+            "{}img_sent/raid_{}_{}_{}_{}{}.{}",
+            CONFIG.load().images.bot,
             now.with_timezone(&Rome).format("%Y%m%d%H"),
             self.raid.gym_id,
             self.raid.start,
-            self.raid.pokemon_id.map(|i| i.to_string()).unwrap_or_default()
+            self.raid.pokemon_id.map(|i| i.to_string()).unwrap_or_default(),
+            get_overlay_suffix(self.get_overlay()),
+            CONFIG.load().images.format.get_extension()
         );
 
         IMG_CACHE
             .get(img_path_str.into(), |img_path| async move {
                 if img_path.exists() {
-                    if let Some(url) = &CONFIG.images.bot_pub {
-                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)));
+                    if let Some(url) = &CONFIG.load().images.bot_pub {
+                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(
+                            &CONFIG.load().images.bot,
+                            url,
+                            1,
+                        )));
                     } else {
                         let mut image = File::open(&img_path)
                             .await
@@ -811,11 +1266,11 @@ impl Message for RaidMessage {
                 }
 
                 let f_cal1 = {
-                    let font = format!("{}fonts/calibri.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibri.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 let f_cal2 = {
-                    let font = format!("{}fonts/calibrib.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibrib.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 let scale11 = rusttype::Scale::uniform(16f32);
@@ -825,13 +1280,11 @@ impl Message for RaidMessage {
                 let (mut background, pokemon) = match self.raid.pokemon_id {
                     Some(pkmn_id) if pkmn_id > 0 => {
                         // $mBg = imagecreatefrompng("images/msg-bgs/msg-raid-big-t" . $v_team . ".png");
-                        let path: PathBuf = format!(
-                            "{}images/msg-bgs/msg-raid-big-t{}{}.png",
-                            CONFIG.images.sender,
+                        let path = theme::get_background(&format!(
+                            "msg-raid-big-t{}{}.png",
                             self.raid.team_id.get_id(),
                             if self.raid.ex_raid_eligible == Some(true) { "-ex" } else { "" }
-                        )
-                        .into();
+                        ));
                         let mut background = open_image(&path).await?;
 
                         let evo = match self.raid.evolution {
@@ -846,21 +1299,29 @@ impl Message for RaidMessage {
                             Some(form) if form > 0 => {
                                 let image: PathBuf = format!(
                                     "{}img/pkmns/shuffle/{}-{}{}.png",
-                                    CONFIG.images.assets, pkmn_id, form, evo
+                                    CONFIG.load().images.assets,
+                                    pkmn_id,
+                                    form,
+                                    evo
                                 )
                                 .into();
                                 match open_image(&image).await {
                                     Ok(img) => img,
                                     Err(_) => {
-                                        let image: PathBuf =
-                                            format!("{}img/pkmns/shuffle/{}{}.png", CONFIG.images.assets, pkmn_id, evo)
-                                                .into();
+                                        let image: PathBuf = format!(
+                                            "{}img/pkmns/shuffle/{}{}.png",
+                                            CONFIG.load().images.assets,
+                                            pkmn_id,
+                                            evo
+                                        )
+                                        .into();
                                         match open_image(&image).await {
                                             Ok(img) => img,
                                             Err(_) => {
                                                 let image: PathBuf = format!(
                                                     "{}img/pkmns/shuffle/{}.png",
-                                                    CONFIG.images.assets, pkmn_id
+                                                    CONFIG.load().images.assets,
+                                                    pkmn_id
                                                 )
                                                 .into();
                                                 open_image(&image).await?
@@ -871,12 +1332,14 @@ impl Message for RaidMessage {
                             }
                             _ => {
                                 let image: PathBuf =
-                                    format!("{}img/pkmns/shuffle/{}{}.png", CONFIG.images.assets, pkmn_id, evo).into();
+                                    format!("{}img/pkmns/shuffle/{}{}.png", CONFIG.load().images.assets, pkmn_id, evo)
+                                        .into();
                                 match open_image(&image).await {
                                     Ok(img) => img,
                                     Err(_) => {
                                         let image: PathBuf =
-                                            format!("{}img/pkmns/shuffle/{}.png", CONFIG.images.assets, pkmn_id).into();
+                                            format!("{}img/pkmns/shuffle/{}.png", CONFIG.load().images.assets, pkmn_id)
+                                                .into();
                                         open_image(&image).await?
                                     }
                                 }
@@ -886,7 +1349,7 @@ impl Message for RaidMessage {
                         if let Some(Gender::Male | Gender::Female) = self.raid.gender {
                             let path: PathBuf = format!(
                                 "{}img/{}.png",
-                                CONFIG.images.assets,
+                                CONFIG.load().images.assets,
                                 if self.raid.gender == Some(Gender::Female) { "female" } else { "male" }
                             )
                             .into();
@@ -1001,18 +1464,16 @@ impl Message for RaidMessage {
                     }
                     _ => {
                         let mut background = {
-                            let path: PathBuf = format!(
-                                "{}images/msg-bgs/msg-raid-sm-t{}{}.png",
-                                CONFIG.images.sender,
+                            let path = theme::get_background(&format!(
+                                "msg-raid-sm-t{}{}.png",
                                 self.raid.team_id.get_id(),
                                 if self.raid.ex_raid_eligible == Some(true) { "-ex" } else { "" }
-                            )
-                            .into();
+                            ));
                             open_image(&path).await?
                         };
                         let pokemon = {
                             let path: PathBuf =
-                                format!("{}images/raid_{}.png", CONFIG.images.sender, self.raid.level).into();
+                                format!("{}images/raid_{}.png", CONFIG.load().images.sender, self.raid.level).into();
                             open_image(&path).await?
                         };
 
@@ -1068,10 +1529,10 @@ impl Message for RaidMessage {
                     },
                 );
 
-                let bytes = save_image(&background, &img_path).await?;
+                let bytes = save_sent_image(&background, &img_path).await?;
 
-                if let Some(url) = &CONFIG.images.bot_pub {
-                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)))
+                if let Some(url) = &CONFIG.load().images.bot_pub {
+                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)))
                 } else {
                     Ok(Image::Bytes(bytes))
                 }
@@ -1092,12 +1553,112 @@ impl Message for RaidMessage {
         conn.query_drop(query).await.map_err(|e| error!("MySQL query error: insert sent raid\n{}", e))?;
         Ok(())
     }
+
+    async fn replaces(&self, chat_id: &str) -> Option<i64> {
+        sent::take_egg(chat_id, &self.raid).await
+    }
+
+    async fn after_send(&self, chat_id: &str, message_id: Option<i64>) -> Result<(), ()> {
+        if let Some(message_id) = message_id {
+            sent::add_egg(chat_id, &self.raid, message_id).await.ok();
+        }
+        match self.reminders {
+            Some(settings) => reminders::schedule(chat_id, &self.raid, settings).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RaidReminderMessage {
+    pub reminder: RaidReminder,
+}
+
+#[async_trait]
+impl Message for RaidReminderMessage {
+    fn get_latitude(&self) -> f64 {
+        self.reminder.raid.latitude
+    }
+
+    fn get_longitude(&self) -> f64 {
+        self.reminder.raid.longitude
+    }
+
+    fn get_expire(&self) -> Option<i64> {
+        Some(self.reminder.raid.end)
+    }
+
+    async fn get_caption(&self) -> Result<String, ()> {
+        let raid = &self.reminder.raid;
+        let now = Utc::now().timestamp();
+        let alarm_icon =
+            String::from_utf8(vec![0xe2, 0x8f, 0xb0]).map_err(|e| error!("error parsing alarm icon: {}", e))?;
+        let boss =
+            raid.pokemon_id
+                .and_then(|id| if id > 0 { LIST.load().get(&id).map(|p| p.name.to_uppercase()) } else { None });
+        let (title, time_label, time) = match self.reminder.kind {
+            ReminderKind::Hatching => (
+                format!(
+                    "{} RAID liv. {} schiude tra {} minuti",
+                    alarm_icon,
+                    raid.level,
+                    ((raid.start - now) as f64 / 60_f64).round()
+                ),
+                "Schiude",
+                raid.start,
+            ),
+            ReminderKind::Hatch => (
+                format!(
+                    "{} RAID {}{} schiuso",
+                    String::from_utf8(vec![0xe2, 0x9a, 0x94, 0xef, 0xb8, 0x8f])
+                        .map_err(|e| error!("error parsing raid icon: {}", e))?,
+                    boss.unwrap_or_default(),
+                    get_mega_desc(&raid.evolution)
+                ),
+                "Termina",
+                raid.end,
+            ),
+            ReminderKind::Ending => (
+                format!(
+                    "{} RAID {} termina tra {} minuti",
+                    alarm_icon,
+                    boss.unwrap_or_else(|| format!("liv. {}", raid.level)),
+                    ((raid.end - now) as f64 / 60_f64).round()
+                ),
+                "Termina",
+                raid.end,
+            ),
+        };
+
+        Ok(format!(
+            "{}\n{} {}\n{} {}: {}",
+            title,
+            String::from_utf8(if raid.ex_raid_eligible == Some(true) {
+                vec![0xE2, 0x9B, 0xB3]
+            } else {
+                vec![0xf0, 0x9f, 0x93, 0x8d]
+            })
+            .map_err(|e| error!("error parsing POI icon: {}", e))?,
+            raid.gym_name,
+            String::from_utf8(vec![0xf0, 0x9f, 0x95, 0x92]).map_err(|e| error!("error parsing clock icon: {}", e))?,
+            time_label,
+            Utc.timestamp_opt(time, 0).single().ok_or(())?.with_timezone(&Rome).format("%T")
+        ))
+    }
+
+    async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
+        // same image of the raid notification, with the boss if it has been revealed
+        RaidMessage { raid: self.reminder.raid.clone(), distance: 0_f64, debug: None, reminders: None, overlay: None }
+            ._get_image(map)
+            .await
+    }
 }
 
 #[derive(Debug)]
 pub struct LureMessage {
     pub pokestop: Pokestop,
     pub debug: Option<String>,
+    pub overlay: Option<Overlay>,
 }
 
 #[async_trait]
@@ -1110,6 +1671,30 @@ impl Message for LureMessage {
         self.pokestop.longitude
     }
 
+    fn get_overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    fn get_expire(&self) -> Option<i64> {
+        self.pokestop.lure_expiration
+    }
+
+    fn get_digest_line(&self) -> Option<String> {
+        Some(format!(
+            "Modulo Esca a {} fino alle {}",
+            self.pokestop.name.as_deref().unwrap_or("Pokéstop"),
+            format_time(self.pokestop.lure_expiration?)?
+        ))
+    }
+
+    fn get_template_values(&self) -> Option<(&'static str, template::Values)> {
+        let mut values = template::Values::new();
+        values.insert("name", self.pokestop.name.clone().unwrap_or_default());
+        values.insert("lure_id", self.pokestop.lure_id?.to_string());
+        values.insert("expire", format_time(self.pokestop.lure_expiration?)?);
+        Some(("lure", values))
+    }
+
     /**
      * 501 => "Modulo Esca",
      * 502 => "Modulo Esca Glaciale",
@@ -1121,18 +1706,21 @@ impl Message for LureMessage {
         if let (Some(timestamp), Some(lure_id)) = (self.pokestop.lure_expiration, self.pokestop.lure_id) {
             let caption = format!(
                 "{} {}\n{} {}\n{} {}",
-                match lure_id {
-                    501 => String::from_utf8(vec![0xE2, 0x98, 0xA2])
-                        .map_err(|e| error!("error parsing lure icon: {}", e))?,
-                    502 => String::from_utf8(vec![0xE2, 0x9D, 0x84])
-                        .map_err(|e| error!("error parsing glacial lure icon: {}", e))?,
-                    503 => String::from_utf8(vec![0xF0, 0x9F, 0x8D, 0x83])
-                        .map_err(|e| error!("error parsing mossy lure icon: {}", e))?,
-                    504 => String::from_utf8(vec![0xF0, 0x9F, 0xA7, 0xB2])
-                        .map_err(|e| error!("error parsing magnetic lure icon: {}", e))?,
-                    505 => String::from_utf8(vec![0xF0, 0x9F, 0x8C, 0xA7])
-                        .map_err(|e| error!("error parsing rainy lure icon: {}", e))?,
-                    _ => String::new(),
+                match theme::get_icon("lure") {
+                    Some(icon) => icon,
+                    None => match lure_id {
+                        501 => String::from_utf8(vec![0xE2, 0x98, 0xA2])
+                            .map_err(|e| error!("error parsing lure icon: {}", e))?,
+                        502 => String::from_utf8(vec![0xE2, 0x9D, 0x84])
+                            .map_err(|e| error!("error parsing glacial lure icon: {}", e))?,
+                        503 => String::from_utf8(vec![0xF0, 0x9F, 0x8D, 0x83])
+                            .map_err(|e| error!("error parsing mossy lure icon: {}", e))?,
+                        504 => String::from_utf8(vec![0xF0, 0x9F, 0xA7, 0xB2])
+                            .map_err(|e| error!("error parsing magnetic lure icon: {}", e))?,
+                        505 => String::from_utf8(vec![0xF0, 0x9F, 0x8C, 0xA7])
+                            .map_err(|e| error!("error parsing rainy lure icon: {}", e))?,
+                        _ => String::new(),
+                    },
                 },
                 match lure_id {
                     501 => "Modulo Esca",
@@ -1161,18 +1749,24 @@ impl Message for LureMessage {
     async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
         let now = Utc::now();
         let img_path_str = format!(
-            "{}img_sent/lure_{}_{}_{}.png",
-            CONFIG.images.bot,
+            "{}img_sent/lure_{}_{}_{}{}.{}",
+            CONFIG.load().images.bot,
             now.with_timezone(&Rome).format("%Y%m%d%H"),
             self.pokestop.pokestop_id,
-            self.pokestop.lure_id.unwrap_or_default()
+            self.pokestop.lure_id.unwrap_or_default(),
+            get_overlay_suffix(self.get_overlay()),
+            CONFIG.load().images.format.get_extension()
         );
 
         IMG_CACHE
             .get(img_path_str.into(), |img_path| async move {
                 if img_path.exists() {
-                    if let Some(url) = &CONFIG.images.bot_pub {
-                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)));
+                    if let Some(url) = &CONFIG.load().images.bot_pub {
+                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(
+                            &CONFIG.load().images.bot,
+                            url,
+                            1,
+                        )));
                     } else {
                         let mut image = File::open(&img_path)
                             .await
@@ -1187,11 +1781,11 @@ impl Message for LureMessage {
                 }
 
                 // let f_cal1 = {
-                //     let font = format!("{}fonts/calibri.ttf", CONFIG.images.sender);
+                //     let font = format!("{}fonts/calibri.ttf", CONFIG.load().images.sender);
                 //     open_font(&font).await?
                 // };
                 let f_cal2 = {
-                    let font = format!("{}fonts/calibrib.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibrib.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 // let scale11 = rusttype::Scale::uniform(16f32);
@@ -1200,14 +1794,17 @@ impl Message for LureMessage {
                 // let scale18 = rusttype::Scale::uniform(23f32);
 
                 let mut background = {
-                    let path: PathBuf = format!("{}images/msg-bgs/msg-lure.png", CONFIG.images.sender).into();
+                    let path = theme::get_background("msg-lure.png");
                     open_image(&path).await?
                 };
 
                 let icon = {
-                    let path: PathBuf =
-                        format!("{}img/items/{}.png", CONFIG.images.assets, self.pokestop.lure_id.unwrap_or_default())
-                            .into();
+                    let path: PathBuf = format!(
+                        "{}img/items/{}.png",
+                        CONFIG.load().images.assets,
+                        self.pokestop.lure_id.unwrap_or_default()
+                    )
+                    .into();
                     open_image(&path).await?
                 };
                 image::imageops::overlay(&mut background, &icon, 5, 5);
@@ -1237,10 +1834,10 @@ impl Message for LureMessage {
 
                 image::imageops::overlay(&mut background, &map, 0, 58);
 
-                let bytes = save_image(&background, &img_path).await?;
+                let bytes = save_sent_image(&background, &img_path).await?;
 
-                if let Some(url) = &CONFIG.images.bot_pub {
-                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)))
+                if let Some(url) = &CONFIG.load().images.bot_pub {
+                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)))
                 } else {
                     Ok(Image::Bytes(bytes))
                 }
@@ -1253,6 +1850,7 @@ impl Message for LureMessage {
 pub struct InvasionMessage {
     pub invasion: Pokestop,
     pub debug: Option<String>,
+    pub overlay: Option<Overlay>,
 }
 
 #[async_trait]
@@ -1265,11 +1863,46 @@ impl Message for InvasionMessage {
         self.invasion.longitude
     }
 
+    fn get_overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    fn get_expire(&self) -> Option<i64> {
+        self.invasion.incident_expire_timestamp
+    }
+
+    fn get_digest_line(&self) -> Option<String> {
+        Some(format!(
+            "Invasione a {} fino alle {}",
+            self.invasion.name.as_deref().unwrap_or("Pokéstop"),
+            format_time(self.invasion.incident_expire_timestamp?)?
+        ))
+    }
+
+    fn get_template_values(&self) -> Option<(&'static str, template::Values)> {
+        let mut values = template::Values::new();
+        values.insert("name", self.invasion.name.clone().unwrap_or_default());
+        values.insert("expire", format_time(self.invasion.incident_expire_timestamp?)?);
+        if let Some(id) = self.invasion.get_grunt_type() {
+            values.insert("grunt_type", id.to_string());
+            if let Some(grunt) = GRUNTS.load().get(&id) {
+                values.insert("grunt", grunt.name.clone());
+                values.insert("sex", grunt.sex.clone().unwrap_or_default());
+                values.insert("element", grunt.element.clone().unwrap_or_default());
+                values.insert("rewards", grunt.get_rewards().map(|id| id.to_string()).collect::<Vec<_>>().join(","));
+            }
+        }
+        Some(("invasion", values))
+    }
+
     async fn get_caption(&self) -> Result<String, ()> {
         if let Some(timestamp) = self.invasion.incident_expire_timestamp {
             let caption = format!(
                 "{} {}\n{} {}\n{} {}",
-                String::from_utf8(vec![0xC2, 0xAE]).map_err(|e| error!("error parsing R icon: {}", e))?,
+                match theme::get_icon("invasion") {
+                    Some(icon) => icon,
+                    None => String::from_utf8(vec![0xC2, 0xAE]).map_err(|e| error!("error parsing R icon: {}", e))?,
+                },
                 match self.invasion.get_grunt_type() {
                     Some(id) => {
                         let grunts = GRUNTS.load();
@@ -1285,6 +1918,34 @@ impl Message for InvasionMessage {
                 Utc.timestamp_opt(timestamp, 0).single().ok_or(())?.with_timezone(&Rome).format("%T")
             );
 
+            let mut caption = caption;
+            let grunts = GRUNTS.load();
+            if let Some(grunt) = self.invasion.get_grunt_type().and_then(|id| grunts.get(&id)) {
+                let list = LIST.load();
+                let names = |ids: &mut dyn Iterator<Item = u16>| {
+                    ids.map(|id| list.get(&id).map(|p| p.name.clone()).unwrap_or_else(|| format!("#{}", id)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                if !grunt.lineup.is_empty() {
+                    write!(
+                        caption,
+                        "\nSquadra: {}",
+                        grunt
+                            .lineup
+                            .iter()
+                            .map(|slot| names(&mut slot.iter().copied()))
+                            .collect::<Vec<_>>()
+                            .join(" | ")
+                    )
+                    .map_err(|_| ())?;
+                }
+                let rewards = names(&mut grunt.get_rewards());
+                if !rewards.is_empty() {
+                    write!(caption, "\nRicompensa: {}", rewards).map_err(|_| ())?;
+                }
+            }
+
             Ok(match self.debug {
                 Some(ref s) => format!("{}\n\n{}", caption, s),
                 None => caption,
@@ -1297,18 +1958,24 @@ impl Message for InvasionMessage {
     async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
         let now = Utc::now();
         let img_path_str = format!(
-            "{}img_sent/invasion_{}_{}_{}.png",
-            CONFIG.images.bot,
+            "{}img_sent/invasion_{}_{}_{}{}.{}",
+            CONFIG.load().images.bot,
             now.with_timezone(&Rome).format("%Y%m%d%H"),
             self.invasion.pokestop_id,
-            self.invasion.get_grunt_type().map(|id| id.to_string()).unwrap_or_default()
+            self.invasion.get_grunt_type().map(|id| id.to_string()).unwrap_or_default(),
+            get_overlay_suffix(self.get_overlay()),
+            CONFIG.load().images.format.get_extension()
         );
 
         IMG_CACHE
             .get(img_path_str.into(), |img_path| async move {
                 if img_path.exists() {
-                    if let Some(url) = &CONFIG.images.bot_pub {
-                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)));
+                    if let Some(url) = &CONFIG.load().images.bot_pub {
+                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(
+                            &CONFIG.load().images.bot,
+                            url,
+                            1,
+                        )));
                     } else {
                         let mut image = File::open(&img_path)
                             .await
@@ -1323,11 +1990,11 @@ impl Message for InvasionMessage {
                 }
 
                 // let f_cal1 = {
-                //     let font = format!("{}fonts/calibri.ttf", CONFIG.images.sender);
+                //     let font = format!("{}fonts/calibri.ttf", CONFIG.load().images.sender);
                 //     open_font(&font).await?
                 // };
                 let f_cal2 = {
-                    let font = format!("{}fonts/calibrib.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibrib.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 // let scale11 = rusttype::Scale::uniform(16f32);
@@ -1336,7 +2003,7 @@ impl Message for InvasionMessage {
                 // let scale18 = rusttype::Scale::uniform(23f32);
 
                 let mut background = {
-                    let path: PathBuf = format!("{}images/msg-bgs/msg-invasion.png", CONFIG.images.sender).into();
+                    let path = theme::get_background("msg-invasion.png");
                     open_image(&path).await?
                 };
 
@@ -1345,7 +2012,8 @@ impl Message for InvasionMessage {
                     if let Some(grunt) = lock.get(&id) {
                         if let Some(sex) = &grunt.sex {
                             let icon = {
-                                let path: PathBuf = format!("{}img/grunts/{}.png", CONFIG.images.assets, sex).into();
+                                let path: PathBuf =
+                                    format!("{}img/grunts/{}.png", CONFIG.load().images.assets, sex).into();
                                 open_image(&path).await?
                             };
                             image::imageops::overlay(&mut background, &icon, 5, 5);
@@ -1355,7 +2023,7 @@ impl Message for InvasionMessage {
                             let icon = {
                                 let path: PathBuf = format!(
                                     "{}img/pkmns/types/{}{}.png",
-                                    CONFIG.images.assets,
+                                    CONFIG.load().images.assets,
                                     &element[0..1].to_uppercase(),
                                     &element[1..]
                                 )
@@ -1370,6 +2038,23 @@ impl Message for InvasionMessage {
                             ));
                             image::imageops::overlay(&mut background, &icon, 32, 32);
                         }
+
+                        // reward encounters, right aligned below the Pokéstop name
+                        let rewards = grunt.get_rewards().collect::<Vec<_>>();
+                        for (i, id) in rewards.iter().rev().take(5).enumerate() {
+                            let path: PathBuf =
+                                format!("{}img/pkmns/shuffle/{}.png", CONFIG.load().images.assets, id).into();
+                            // a missing icon shouldn't prevent the notification
+                            if let Ok(icon) = open_image(&path).await {
+                                let icon = image::DynamicImage::ImageRgba8(image::imageops::resize(
+                                    &icon,
+                                    24,
+                                    24,
+                                    image::imageops::FilterType::Triangle,
+                                ));
+                                image::imageops::overlay(&mut background, &icon, 251 - 26 * i as u32, 32);
+                            }
+                        }
                     }
                 }
 
@@ -1398,10 +2083,10 @@ impl Message for InvasionMessage {
 
                 image::imageops::overlay(&mut background, &map, 0, 58);
 
-                let bytes = save_image(&background, &img_path).await?;
+                let bytes = save_sent_image(&background, &img_path).await?;
 
-                if let Some(url) = &CONFIG.images.bot_pub {
-                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)))
+                if let Some(url) = &CONFIG.load().images.bot_pub {
+                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)))
                 } else {
                     Ok(Image::Bytes(bytes))
                 }
@@ -1413,7 +2098,8 @@ impl Message for InvasionMessage {
 #[derive(Debug)]
 pub struct WeatherMessage {
     pub watch: Watch,
-    // pub actual_weather: Weather,
+    pub reference_weather: Option<u8>,
+    pub actual_weather: u8,
     pub debug: Option<String>,
 }
 
@@ -1428,14 +2114,48 @@ impl Message for WeatherMessage {
     }
 
     async fn get_caption(&self) -> Result<String, ()> {
-        // let old = self.watch.reference_weather.as_ref().ok_or_else(|| error!("reference_weather is None"))?;
-        let caption = format!(
-            "{} Meteo cambiato nella cella!",
+        let (name, boost) = {
+            let lock = LIST.load();
+            let pokemon = lock.get(&self.watch.pokemon_id);
+            (
+                pokemon.map(|p| p.name.to_uppercase()).unwrap_or_default(),
+                // boost before and after the change, when the Pokémon types are known
+                pokemon
+                    .filter(|p| p.ptype.is_some())
+                    .zip(self.reference_weather)
+                    .map(|(p, old)| (is_boosted(p, old), is_boosted(p, self.actual_weather))),
+            )
+        };
+        let mut caption = format!(
+            "{} Meteo cambiato nella cella!\n{}{}\n",
             String::from_utf8(vec![0xE2, 0x9B, 0x85]).map_err(|e| error!("error encoding meteo icon: {}", e))?,
-            // if old == &self.actual_weather { "invariato" } else { "cambiato" }
+            name,
+            self.watch.iv.map(|iv| format!(" ({}%)", iv)).unwrap_or_default(),
         );
+        match self.reference_weather {
+            Some(old) => caption.push_str(&format!(
+                "Prima:{} | Adesso:{}",
+                meteo_icon(old).unwrap_or_default(),
+                meteo_icon(self.actual_weather).unwrap_or_default()
+            )),
+            None => caption.push_str(&format!("Adesso:{}", meteo_icon(self.actual_weather).unwrap_or_default())),
+        }
+        if let Some(boost) = boost {
+            match boost {
+                (false, true) => caption.push_str("\nOra è potenziato dal meteo"),
+                // the encounter gets new stats once the boost ends
+                (true, false) => write!(
+                    caption,
+                    "\n{} Non è più potenziato dal meteo: IV e livello potrebbero essere cambiati",
+                    String::from_utf8(vec![0xe2, 0x9a, 0xa0, 0xef, 0xb8, 0x8f])
+                        .map_err(|e| error!("error encoding warning icon: {}", e))?
+                )
+                .map_err(|_| ())?,
+                _ => caption.push_str("\nIl potenziamento meteo non è cambiato"),
+            }
+        }
         Ok(match &self.debug {
-            Some(time) => format!("{}\n\nScansione avvenuta alle {}", caption, time), //, old.diff(&self.actual_weather)),
+            Some(time) => format!("{}\n\nScansione avvenuta alle {}", caption, time),
             _ => caption,
         })
     }
@@ -1447,12 +2167,13 @@ impl Message for WeatherMessage {
     async fn get_image(&self) -> Result<Image, ()> {
         let timestamp = Utc.timestamp_opt(self.watch.expire, 0).single().ok_or(())?;
         let img_path_str = format!(
-            "{}img_sent/poke_{}_{}_{}_{}.png",
-            CONFIG.images.bot,
+            "{}img_sent/poke_{}_{}_{}_{}.{}",
+            CONFIG.load().images.bot,
             timestamp.with_timezone(&Rome).format("%Y%m%d%H"),
             self.watch.encounter_id,
             self.watch.pokemon_id,
-            self.watch.iv.map(|iv| format!("{:.0}", iv)).unwrap_or_default()
+            self.watch.iv.map(|iv| format!("{:.0}", iv)).unwrap_or_default(),
+            CONFIG.load().images.format.get_extension()
         );
 
         // no need for OnceBarrier
@@ -1473,48 +2194,51 @@ impl Message for WeatherMessage {
         }
     }
 
-    // fn message_button(&self, _chat_id: &str, mtype: &str) -> Result<Value, ()> {
-    //     let lat = self.get_latitude();
-    //     let lon = self.get_longitude();
-
-    //     let maplink = match mtype {
-    //         "g" => format!("https://maps.google.it/maps/?q={},{}", lat, lon),
-    //         "g2" => format!("https://www.google.it/maps/place/{},{}", lat, lon),
-    //         "g3" => format!("https://www.google.com/maps/search/?api=1&query={},{}", lat, lon),
-    //         "gd" => format!("https://www.google.com/maps/dir/?api=1&destination={},{}", lat, lon),
-    //         "a" => format!("http://maps.apple.com/?address={},{}", lat, lon),
-    //         "w" => format!("https://waze.com/ul?ll={},{}", lat, lon),
-    //         _ => format!("https://maps.google.it/maps/?q={},{}", lat, lon),
-    //     };
-    //     let title = format!("{} Mappa", String::from_utf8(vec![0xf0, 0x9f, 0x8c, 0x8e]).map_err(|e| error!("error encoding map icon: {}", e))?);
-
-    //     let mut keyboard = json!({
-    //             "inline_keyboard": [[{
-    //                 "text": title,
-    //                 "url": maplink
-    //             }]]
-    //         });
-
-    //     // watch button available only on crossing-hour spawns
-    //     if self.debug.is_some() {
-    //         if let Some(a) = keyboard["inline_keyboard"].as_array_mut() {
-    //             a.push(json!([{
-    //                 "text": format!("{} Ferma tracciamento Meteo", String::from_utf8(vec![0xE2, 0x9B, 0x85]).map_err(|e| error!("error encoding meteo icon: {}", e))?),
-    //                 "callback_data": format!("stop|{:.3}|{:.3}|{}|{}|{}|{}", lat, lon, self.watch.expire, self.watch.encounter_id, self.watch.pokemon_id, self.watch.iv.map(|iv| iv.to_string()).unwrap_or_default())
-    //             }]));
-    //         }
-    //     }
-
-    //     Ok(keyboard)
-    // }
+    fn message_button(&self, _chat_id: &str, mtype: &str) -> Result<Value, ()> {
+        let lat = self.get_latitude();
+        let lon = self.get_longitude();
+
+        let maplink = match mtype {
+            "g" => format!("https://maps.google.it/maps/?q={},{}", lat, lon),
+            "g2" => format!("https://www.google.it/maps/place/{},{}", lat, lon),
+            "g3" => format!("https://www.google.com/maps/search/?api=1&query={},{}", lat, lon),
+            "gd" => format!("https://www.google.com/maps/dir/?api=1&destination={},{}", lat, lon),
+            "a" => format!("http://maps.apple.com/?address={},{}", lat, lon),
+            "w" => format!("https://waze.com/ul?ll={},{}", lat, lon),
+            _ => format!("https://maps.google.it/maps/?q={},{}", lat, lon),
+        };
+        let title = format!(
+            "{} Mappa",
+            String::from_utf8(vec![0xf0, 0x9f, 0x8c, 0x8e]).map_err(|e| error!("error encoding map icon: {}", e))?
+        );
+
+        Ok(json!({
+            "inline_keyboard": [[{
+                "text": title,
+                "url": maplink
+            }], [{
+                "text": format!("{} Ferma tracciamento Meteo", String::from_utf8(vec![0xE2, 0x9B, 0x85]).map_err(|e| error!("error encoding meteo icon: {}", e))?),
+                "callback_data": format!("stop|{:.3}|{:.3}|{}|{}|{}|{}", lat, lon, self.watch.expire, self.watch.encounter_id, self.watch.pokemon_id, self.watch.iv.map(|iv| iv.to_string()).unwrap_or_default())
+            }]]
+        }))
+    }
+
+    async fn after_send(&self, _chat_id: &str, _message_id: Option<i64>) -> Result<(), ()> {
+        BotConfigs::update_watch(&self.watch, self.actual_weather).await
+    }
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct GymMessage {
     pub gym: GymDetails,
+    /// state before this update, if known
+    pub before: Option<GymState>,
+    /// subscribed changes which triggered the notification
+    pub events: Vec<GymEvent>,
     pub distance: f64,
     pub debug: Option<String>,
+    pub overlay: Option<Overlay>,
 }
 
 #[async_trait]
@@ -1527,12 +2251,60 @@ impl Message for GymMessage {
         self.gym.longitude
     }
 
+    fn get_overlay(&self) -> Option<&Overlay> {
+        self.overlay.as_ref()
+    }
+
+    fn get_template_values(&self) -> Option<(&'static str, template::Values)> {
+        let mut values = template::Values::new();
+        values.insert("name", self.gym.name.clone());
+        values.insert("team", self.gym.team.get_id().to_string());
+        values.insert("team_name", get_team_name(self.gym.team.get_id()).to_owned());
+        values.insert("members", (6 - self.gym.slots_available).to_string());
+        values.insert("slots", self.gym.slots_available.to_string());
+        values.insert("ex", u8::from(self.gym.ex_raid_eligible == Some(true)).to_string());
+        if let Some(before) = self.before {
+            values.insert("team_before", before.team.to_string());
+        }
+        Some(("gym", values))
+    }
+
     async fn get_caption(&self) -> Result<String, ()> {
         let caption = format!(
             "{} Situazione cambiata nella palestra {}!",
-            String::from_utf8(vec![0xF0, 0x9F, 0x8F, 0x8B]).map_err(|e| error!("error encoding gym icon: {}", e))?,
+            match theme::get_icon("gym") {
+                Some(icon) => icon,
+                None => String::from_utf8(vec![0xF0, 0x9F, 0x8F, 0x8B])
+                    .map_err(|e| error!("error encoding gym icon: {}", e))?,
+            },
             self.gym.name
         );
+
+        let mut caption = caption;
+        let team = self.gym.team.get_id();
+        match self.before {
+            Some(before) if before.team != team => write!(
+                caption,
+                "\nSquadra: {} {} {}",
+                get_team_name(before.team),
+                String::from_utf8(vec![0xE2, 0x9E, 0xA1, 0xEF, 0xB8, 0x8F])
+                    .map_err(|e| error!("error encoding arrow icon: {}", e))?,
+                get_team_name(team)
+            ),
+            _ => write!(caption, "\nSquadra: {}", get_team_name(team)),
+        }
+        .map_err(|_| ())?;
+        write!(caption, "\nPosti liberi: {}", self.gym.slots_available).map_err(|_| ())?;
+        if self.events.contains(&GymEvent::UnderAttack) {
+            write!(
+                caption,
+                "\n{} Sotto attacco!",
+                String::from_utf8(vec![0xE2, 0x9A, 0x94, 0xEF, 0xB8, 0x8F])
+                    .map_err(|e| error!("error encoding battle icon: {}", e))?
+            )
+            .map_err(|_| ())?;
+        }
+
         Ok(match &self.debug {
             Some(time) => format!("{}\n\n{}", caption, time),
             _ => caption,
@@ -1542,20 +2314,26 @@ impl Message for GymMessage {
     async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
         let now = Utc::now();
         let img_path_str = format!(
-            "{}img_sent/gym_{}_{}_{}_{}_{}.png",
-            CONFIG.images.bot,
+            "{}img_sent/gym_{}_{}_{}_{}_{}{}.{}",
+            CONFIG.load().images.bot,
             now.with_timezone(&Rome).format("%Y%m%d%H"),
             self.gym.id,
             self.gym.team.get_id(),
             6 - self.gym.slots_available,
-            u8::from(self.gym.ex_raid_eligible == Some(true))
+            u8::from(self.gym.ex_raid_eligible == Some(true)),
+            get_overlay_suffix(self.get_overlay()),
+            CONFIG.load().images.format.get_extension()
         );
 
         IMG_CACHE
             .get(img_path_str.into(), |img_path| async move {
                 if img_path.exists() {
-                    if let Some(url) = &CONFIG.images.bot_pub {
-                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)));
+                    if let Some(url) = &CONFIG.load().images.bot_pub {
+                        return Ok(Image::FileUrl(img_path.display().to_string().replacen(
+                            &CONFIG.load().images.bot,
+                            url,
+                            1,
+                        )));
                     } else {
                         let mut image = File::open(&img_path)
                             .await
@@ -1570,11 +2348,11 @@ impl Message for GymMessage {
                 }
 
                 // let f_cal1 = {
-                //     let font = format!("{}fonts/calibri.ttf", CONFIG.images.sender);
+                //     let font = format!("{}fonts/calibri.ttf", CONFIG.load().images.sender);
                 //     open_font(&font).await?
                 // };
                 let f_cal2 = {
-                    let font = format!("{}fonts/calibrib.ttf", CONFIG.images.sender);
+                    let font = format!("{}fonts/calibrib.ttf", CONFIG.load().images.sender);
                     open_font(&font).await?
                 };
                 // let scale11 = rusttype::Scale::uniform(16f32);
@@ -1582,19 +2360,17 @@ impl Message for GymMessage {
                 // let scale18 = rusttype::Scale::uniform(23f32);
 
                 let mut background = {
-                    let path: PathBuf = format!(
-                        "{}images/msg-bgs/msg-raid-sm-t{}{}.png",
-                        CONFIG.images.sender,
+                    let path = theme::get_background(&format!(
+                        "msg-raid-sm-t{}{}.png",
                         self.gym.team.get_id(),
                         if self.gym.ex_raid_eligible == Some(true) { "-ex" } else { "" }
-                    )
-                    .into();
+                    ));
                     open_image(&path).await?
                 };
                 let gym = {
                     let path: PathBuf = format!(
                         "{}img/pkmns/gym_images/t{}m{}p{}.png",
-                        CONFIG.images.assets,
+                        CONFIG.load().images.assets,
                         self.gym.team.get_id(),
                         6 - self.gym.slots_available,
                         u8::from(self.gym.ex_raid_eligible == Some(true))
@@ -1619,10 +2395,10 @@ impl Message for GymMessage {
                 // imagecopymerge($mBg, $mMap, 0, ($v_pkmnid == 0 ? 83 : 136), 0, 0, 280, 101, 100);
                 image::imageops::overlay(&mut background, &map, 0, 83);
 
-                let bytes = save_image(&background, &img_path).await?;
+                let bytes = save_sent_image(&background, &img_path).await?;
 
-                if let Some(url) = &CONFIG.images.bot_pub {
-                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.images.bot, url, 1)))
+                if let Some(url) = &CONFIG.load().images.bot_pub {
+                    Ok(Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)))
                 } else {
                     Ok(Image::Bytes(bytes))
                 }
@@ -1641,6 +2417,7 @@ impl<'a> Message for DeviceTierMessage<'a> {
     async fn send(&self, chat_id: &str, image: Image, _: &str) -> Result<(), ()> {
         send_photo(
             CONFIG
+                .load()
                 .telegram
                 .alert_bot_token
                 .as_ref()
@@ -1704,7 +2481,7 @@ impl<'a> Message for DeviceTierMessage<'a> {
                 .max_dimensions(400, 400)
                 .build();
 
-        let path: PathBuf = format!("{}img/logo.png", CONFIG.images.assets).into();
+        let path: PathBuf = format!("{}img/logo.png", CONFIG.load().images.assets).into();
         let logo = open_image(&path).await?;
 
         image::imageops::overlay(&mut image, &logo, 150, 150);
@@ -1717,6 +2494,84 @@ impl<'a> Message for DeviceTierMessage<'a> {
     }
 }
 
+/// Notifications matched during the digest window of a user, sent as a single message
+pub struct DigestMessage {
+    pub messages: Vec<Box<dyn Message + Send + Sync>>,
+    /// summary of the notifications matched during quiet hours
+    pub away: bool,
+    /// notifications dropped to limit the summary size
+    pub skipped: usize,
+}
+
+impl DigestMessage {
+    fn get_points(&self) -> Vec<(f64, f64)> {
+        self.messages.iter().map(|m| (m.get_latitude(), m.get_longitude())).collect()
+    }
+}
+
+#[async_trait]
+impl Message for DigestMessage {
+    fn get_latitude(&self) -> f64 {
+        self.messages.iter().map(|m| m.get_latitude()).sum::<f64>() / self.messages.len().max(1) as f64
+    }
+
+    fn get_longitude(&self) -> f64 {
+        self.messages.iter().map(|m| m.get_longitude()).sum::<f64>() / self.messages.len().max(1) as f64
+    }
+
+    fn get_expire(&self) -> Option<i64> {
+        self.messages.iter().filter_map(|m| m.get_expire()).max()
+    }
+
+    async fn get_caption(&self) -> Result<String, ()> {
+        let lines = self.messages.iter().filter_map(|m| m.get_digest_line()).collect::<Vec<_>>();
+        let mut caption = if self.away {
+            format!("🌙 <b>Mentre eri via: {} notifiche ancora valide</b>\n", lines.len())
+        } else {
+            format!("📋 <b>{} notifiche raggruppate</b>\n", lines.len())
+        };
+        for (index, line) in lines.iter().take(MAX_DIGEST_LINES).enumerate() {
+            writeln!(caption, "{}. {}", index + 1, line).map_err(|_| ())?;
+        }
+        if lines.len() > MAX_DIGEST_LINES {
+            writeln!(caption, "…e altre {}", lines.len() - MAX_DIGEST_LINES).map_err(|_| ())?;
+        }
+        if self.skipped > 0 {
+            write!(caption, "{} notifiche in scadenza scartate", self.skipped).map_err(|_| ())?;
+        }
+        Ok(caption)
+    }
+
+    async fn get_map(&self) -> Result<image::DynamicImage, ()> {
+        let config = CONFIG.load_full();
+        let map = Map::new(14, 400, 250, self.get_latitude(), self.get_longitude()).with_markers(self.get_points());
+        let marker: PathBuf = format!("{}img/marker.png", config.images.assets).into();
+        map.get_map(open_image(&marker).await.ok()).await
+    }
+
+    async fn _get_image(&self, map: image::DynamicImage) -> Result<Image, ()> {
+        let mut hasher = DefaultHasher::new();
+        for message in &self.messages {
+            message.get_digest_line().hash(&mut hasher);
+        }
+        let img_path: PathBuf = format!(
+            "{}img_sent/{}_{}_{:x}.{}",
+            CONFIG.load().images.bot,
+            if self.away { "away" } else { "digest" },
+            Utc::now().with_timezone(&Rome).format("%Y%m%d%H"),
+            hasher.finish(),
+            CONFIG.load().images.format.get_extension()
+        )
+        .into();
+
+        let bytes = save_sent_image(&map, &img_path).await?;
+        Ok(match &CONFIG.load().images.bot_pub {
+            Some(url) => Image::FileUrl(img_path.display().to_string().replacen(&CONFIG.load().images.bot, url, 1)),
+            None => Image::Bytes(bytes),
+        })
+    }
+}
+
 pub struct LagMessage {
     pub lag: u64,
 }
@@ -1726,6 +2581,7 @@ impl Message for LagMessage {
     async fn send(&self, chat_id: &str, _: Image, _: &str) -> Result<(), ()> {
         send_message(
             CONFIG
+                .load()
                 .telegram
                 .alert_bot_token
                 .as_ref()
@@ -1760,3 +2616,41 @@ impl Message for LagMessage {
         Ok(Image::Bytes(Vec::new()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::telegram::CallResult;
+
+    use super::edit_or_send;
+
+    async fn call(calls: &Mutex<Vec<&'static str>>, name: &'static str, ok: bool) -> Result<String, CallResult> {
+        calls.lock().unwrap().push(name);
+        if ok {
+            Ok(name.to_owned())
+        } else {
+            Err(CallResult::Body((400, String::from("{}"))))
+        }
+    }
+
+    #[tokio::test]
+    async fn replace_fallback() {
+        for (media, caption, send, expected, result) in [
+            (true, true, true, vec!["media"], Some("media")),
+            (false, true, true, vec!["media", "caption"], Some("caption")),
+            (false, false, true, vec!["media", "caption", "send"], Some("send")),
+            (false, false, false, vec!["media", "caption", "send"], None),
+        ] {
+            let calls = Mutex::new(Vec::new());
+            let res = edit_or_send(
+                call(&calls, "media", media),
+                call(&calls, "caption", caption),
+                call(&calls, "send", send),
+            )
+            .await;
+            assert_eq!(calls.into_inner().unwrap(), expected);
+            assert_eq!(res.ok().as_deref(), result);
+        }
+    }
+}
@@ -1,13 +1,11 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
 
 use futures_util::stream::unfold;
 
-use stream_throttle::{ThrottlePool, ThrottleRate, ThrottledStream};
-
 use tokio::{
     spawn,
     sync::{broadcast, Mutex, RwLock, RwLockWriteGuard},
-    time::interval,
+    time::{interval, sleep_until, Instant},
 };
 
 use mysql_async::{from_row, params, prelude::Queryable};
@@ -16,7 +14,7 @@ use chrono::{offset::TimeZone, DateTime, Timelike, Utc};
 
 use lru_time_cache::LruCache;
 
-use geo::Point;
+use geo::{Point, Polygon};
 
 use geo_raycasting::RayCasting;
 
@@ -24,19 +22,32 @@ use once_cell::sync::Lazy;
 
 use tracing::{debug, error, info, warn};
 
-use rocketmap_entities::{DeviceTier, RequestId, Watch};
+use rocketmap_entities::{DeviceTier, RequestId, Watch, Weather};
 
 mod config;
+mod digest;
 mod file_cache;
+mod gyms;
 mod map;
 mod message;
+mod pvp;
+mod reminders;
 mod select_all;
-
-use message::{DeviceTierMessage, LagMessage, Message};
+mod sent;
+mod template;
+mod theme;
+mod throttle;
+mod tiles;
+
+use message::{
+    DeviceTierMessage, GymMessage, InvasionMessage, LagMessage, LureMessage, Message, PokemonMessage, RaidMessage,
+    RaidReminderMessage,
+};
 
 use crate::config::CONFIG;
 use crate::db::MYSQL;
-use crate::telegram::send_message;
+use crate::shutdown::{self, InFlight};
+use crate::telegram::{send_message, Image};
 use crate::{
     lists::{CityStats, FormCache, PokemonCache, CITIES, CITYPARKS, CITYSTATS},
     Platform,
@@ -45,18 +56,35 @@ use crate::{
 type Request = rocketmap_entities::Request<PokemonCache, FormCache>;
 
 static BOT_CONFIGS: Lazy<RwLock<HashMap<String, config::BotConfig>>> = Lazy::new(|| RwLock::new(HashMap::new()));
-static WATCHES: Lazy<RwLock<HashMap<String, Vec<Watch>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static WATCHES: Lazy<RwLock<HashMap<String, Vec<WeatherWatch>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+// last weather seen on every cell, with the hour it refers to
+#[allow(clippy::type_complexity)]
+static CELLS: Lazy<RwLock<HashMap<i64, (Polygon<f64>, u8, u32)>>> = Lazy::new(|| RwLock::new(HashMap::new()));
 #[allow(clippy::type_complexity)]
 static SENDER: Lazy<broadcast::Sender<Arc<(DateTime<Utc>, Platform, Request)>>> = Lazy::new(|| {
-    let (tx, _) = broadcast::channel(CONFIG.service.queue_size);
+    let (tx, _) = broadcast::channel(CONFIG.load().service.queue_size);
     tx
 });
 static SENT_CACHE: Lazy<Mutex<LruCache<String, ()>>> =
     Lazy::new(|| Mutex::new(LruCache::with_expiry_duration(Duration::from_secs(3600)))); //1 hour cache
 
 const RATE_LIMITER_CHECK_INTERVAL: u8 = 10;
+// We can send a single message per telegram chat per second
+const DEFAULT_CHAT_RATE: u32 = 1;
 const MAX_NOTIFICATIONS_PER_HOUR: u32 = 500;
 
+fn chat_rate() -> u32 {
+    CONFIG.load().telegram.chat_rate.unwrap_or(DEFAULT_CHAT_RATE)
+}
+
+/// Weather watch, along with the last known weather of its cell
+#[derive(Clone, Debug)]
+pub struct WeatherWatch {
+    pub watch: Watch,
+    /// weather at spawn time, then updated on every notified change
+    pub weather: Option<u8>,
+}
+
 #[derive(PartialEq)]
 enum LoadResult {
     Ok,
@@ -81,7 +109,10 @@ impl BotConfigs {
             let mut interval = interval(Duration::from_secs(60));
             let mut index: u8 = 0;
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown::wait() => break,
+                }
 
                 //being index an u8, if 255 is not a multiple of RATE_LIMITER_CHECK_INTERVAL we'll get a shift every round
                 index = index.wrapping_add(1);
@@ -141,26 +172,24 @@ impl BotConfigs {
         // load weather watches
         {
             let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
-            let res = conn.query_iter("SELECT user_id, encounter_id, pokemon_id, iv, latitude, longitude, expire FROM bot_weather_watches WHERE expire > UNIX_TIMESTAMP()").await.map_err(|e| error!("MySQL query error: get weather watches\n{}", e))?;
+            let res = conn.query_iter("SELECT user_id, encounter_id, pokemon_id, iv, latitude, longitude, expire, weather FROM bot_weather_watches WHERE expire > UNIX_TIMESTAMP()").await.map_err(|e| error!("MySQL query error: get weather watches\n{}", e))?;
             let mut lock = WATCHES.write().await;
             res.for_each_and_drop(|row| {
-                let (user_id, encounter_id, pokemon_id, iv, latitude, longitude, expire) =
-                    from_row::<(String, String, u16, Option<u8>, f64, f64, i64)>(row);
+                let (user_id, encounter_id, pokemon_id, iv, latitude, longitude, expire, weather) =
+                    from_row::<(String, String, u16, Option<u8>, f64, f64, i64, Option<u8>)>(row);
                 let entry = lock.entry(user_id.clone()).or_insert_with(Vec::new);
-                entry.push(Watch {
-                    user_id,
-                    encounter_id,
-                    pokemon_id,
-                    iv,
-                    point: (latitude, longitude).into(),
-                    expire,
-                    // reference_weather: None,
+                entry.push(WeatherWatch {
+                    watch: Watch { user_id, encounter_id, pokemon_id, iv, point: (latitude, longitude).into(), expire },
+                    weather,
                 });
             })
             .await
             .map_err(|e| error!("MySQL collect error: {}", e))?;
         }
 
+        reminders::init().await?;
+        sent::init();
+
         Ok(())
     }
 
@@ -244,7 +273,7 @@ impl BotConfigs {
         };
 
         spawn(async move {
-            send_message(&CONFIG.telegram.bot_token, &user_id, &msg)
+            send_message(&CONFIG.load().telegram.bot_token, &user_id, &msg)
                 .set_parse_mode("HTML")
                 .send()
                 .await
@@ -335,33 +364,94 @@ impl BotConfigs {
                         *c = config;
                     } else {
                         configs.insert(user_id.clone(), config);
-                        let stream = unfold((SENDER.subscribe(), user_id), |(mut rx, user_id)| {
+                        let state = (
+                            SENDER.subscribe(),
+                            reminders::subscribe(&user_id).await,
+                            user_id,
+                            digest::Digest::default(),
+                            digest::Away::default(),
+                        );
+                        let stream = unfold(state, |(mut rx, mut inbox, user_id, mut digest, mut away)| {
                             Box::pin(async {
                                 let res: select_all::Message;
                                 loop {
-                                    let temp = match rx.recv().await {
-                                        Ok(t) => t,
-                                        Err(broadcast::error::RecvError::Lagged(lag)) => {
-                                            res = (user_id.clone(), Box::new(LagMessage { lag }), String::new());
+                                    let deadline = digest.get_deadline();
+                                    let away_deadline = away.get_deadline();
+                                    let received = tokio::select! {
+                                        received = rx.recv() => Some(received),
+                                        Some((reminder, guard)) = inbox.recv() => {
+                                            let lock = BOT_CONFIGS.read().await;
+                                            let conf = lock.get(&user_id)?;
+                                            // reminders are dropped out of hours, in quiet mode and while snoozed
+                                            if conf.is_awake(&Utc::now()) {
+                                                res = (
+                                                    user_id.clone(),
+                                                    Box::new(RaidReminderMessage { reminder }),
+                                                    conf.more.l.clone(),
+                                                    guard,
+                                                );
+                                                break;
+                                            }
+                                            continue;
+                                        }
+                                        _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => None,
+                                        _ = shutdown::wait(), if deadline.is_some() || away_deadline.is_some() => None,
+                                        _ = sleep_until(away_deadline.unwrap_or_else(Instant::now)), if away_deadline.is_some() => None,
+                                    };
+                                    let temp = match received {
+                                        // digest window expired, shutdown requested or a new minute started
+                                        // on shutdown quiet hours summaries are sent too, they would be lost otherwise
+                                        None => {
+                                            let now = Utc::now();
+                                            let lock = BOT_CONFIGS.read().await;
+                                            let conf = lock.get(&user_id)?;
+                                            let stopping = shutdown::is_shutting_down();
+                                            let taken = if digest.is_due(Instant::now(), stopping) {
+                                                digest.take()
+                                            } else if stopping || !conf.is_quiet(&now) {
+                                                away.take(now.timestamp())
+                                            } else {
+                                                None
+                                            };
+                                            if let Some((msg, guard)) = taken {
+                                                res = (user_id.clone(), msg, conf.more.l.clone(), guard);
+                                                break;
+                                            }
+                                            continue;
+                                        }
+                                        Some(Ok(t)) => t,
+                                        Some(Err(broadcast::error::RecvError::Lagged(lag))) => {
+                                            res = (
+                                                user_id.clone(),
+                                                Box::new(LagMessage { lag }),
+                                                String::new(),
+                                                InFlight::start(),
+                                            );
                                             break;
                                         }
                                         _ => return None,
                                     };
+                                    // the request is no more counted as queued, so track it until it's been processed
+                                    let guard = InFlight::start();
                                     let (time, platform, req) = temp.as_ref();
                                     let lock = BOT_CONFIGS.read().await;
                                     let conf = lock.get(&user_id)?;
-                                    if let Ok(msg) = conf.submit(time, platform, req).await {
-                                        res = (user_id.clone(), msg, conf.more.l.clone());
-                                        break;
+                                    match conf.submit(time, platform, req).await {
+                                        Ok(config::Delivery::Now(msg)) => match conf.more.g.filter(|g| *g > 0) {
+                                            Some(window) if msg.is_frequent() => digest.push(msg, window),
+                                            _ => {
+                                                res = (user_id.clone(), msg, conf.more.l.clone(), guard);
+                                                break;
+                                            }
+                                        },
+                                        Ok(config::Delivery::Away(msg)) => away.push(msg, time.timestamp()),
+                                        Err(_) => (),
                                     }
                                 }
-                                Some((res, (rx, user_id)))
+                                Some((res, (rx, inbox, user_id, digest, away)))
                             })
                         });
-                        // We can send a single message per telegram chat per second
-                        let rate = ThrottleRate::new(1, Duration::from_secs(1));
-                        let pool = ThrottlePool::new(rate);
-                        select_all::add(stream.throttle(pool)).await.ok();
+                        select_all::add(throttle::Throttled::new(stream, chat_rate)).await.ok();
                     }
 
                     Ok(LoadResult::Ok)
@@ -376,13 +466,13 @@ impl BotConfigs {
         }
     }
 
-    async fn clean_watches<'a>(now: i64, watch: &Watch) -> RwLockWriteGuard<'a, HashMap<String, Vec<Watch>>> {
+    async fn clean_watches<'a>(now: i64, watch: &Watch) -> RwLockWriteGuard<'a, HashMap<String, Vec<WeatherWatch>>> {
         // remove expired watches
         let mut lock = WATCHES.write().await;
         for (_, v) in lock.iter_mut() {
             let mut remove = Vec::new();
             for (index, w) in v.iter().enumerate() {
-                if w.expire < now || w == watch {
+                if w.watch.expire < now || &w.watch == watch {
                     remove.push(index);
                 }
             }
@@ -394,6 +484,30 @@ impl BotConfigs {
         lock
     }
 
+    async fn update_cell(weather: &Weather, now: &DateTime<Utc>) {
+        if let Some(id) = weather.id {
+            let mut lock = CELLS.write().await;
+            lock.insert(id, (weather.polygon.clone(), weather.gameplay_condition, now.hour()));
+        }
+    }
+
+    /// Stores the weather notified to the user, so that the next notification is sent only on a new change
+    pub async fn update_watch(watch: &Watch, weather: u8) -> Result<(), ()> {
+        let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+        conn.exec_drop(
+            "UPDATE bot_weather_watches SET weather = :weather WHERE user_id = :user_id AND encounter_id = :encounter_id AND pokemon_id = :pokemon_id AND expire = :expire",
+            params! {
+                "weather" => weather,
+                "user_id" => watch.user_id.clone(),
+                "encounter_id" => watch.encounter_id.clone(),
+                "pokemon_id" => watch.pokemon_id,
+                "expire" => watch.expire,
+            }
+        ).await.map_err(|e| error!("MySQL query error: update weather watch\n{}", e))?;
+
+        Ok(())
+    }
+
     async fn remove_watches(watch: Box<Watch>) -> Result<(), ()> {
         let now = Utc::now().timestamp();
 
@@ -425,14 +539,26 @@ impl BotConfigs {
 
         if watch.expire > now_timestamp
             && now.hour() != watch_timestamp.hour()
-            && lock.get(&watch.user_id).map(|v| v.contains(&watch)) != Some(true)
+            && lock.get(&watch.user_id).map(|v| v.iter().any(|w| w.watch == *watch)) != Some(true)
         {
+            // the watch is started within the spawn hour, so the weather of the cell is the one at spawn time
+            let weather = {
+                let cells = CELLS.read().await;
+                cells.values().find_map(|(polygon, weather, hour)| {
+                    if *hour == now.hour() && polygon.within(&watch.point) {
+                        Some(*weather)
+                    } else {
+                        None
+                    }
+                })
+            };
+
             let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
             conn.query_drop("DELETE FROM bot_weather_watches WHERE expire < UNIX_TIMESTAMP()")
                 .await
                 .map_err(|e| error!("MySQL delete error: {}", e))?;
             conn.exec_drop(
-                "INSERT INTO bot_weather_watches (user_id, encounter_id, pokemon_id, iv, latitude, longitude, expire) VALUES (:user_id, :encounter_id, :pokemon_id, :iv, :latitude, :longitude, :expire)",
+                "INSERT INTO bot_weather_watches (user_id, encounter_id, pokemon_id, iv, latitude, longitude, expire, weather) VALUES (:user_id, :encounter_id, :pokemon_id, :iv, :latitude, :longitude, :expire, :weather)",
                 params! {
                     "user_id" => watch.user_id.clone(),
                     "encounter_id" => watch.encounter_id.clone(),
@@ -441,11 +567,12 @@ impl BotConfigs {
                     "latitude" => watch.point.x(),
                     "longitude" => watch.point.y(),
                     "expire" => watch.expire,
+                    "weather" => weather,
                 }
             ).await.map_err(|e| error!("MySQL insert error: insert weather watch\n{}", e))?;
 
             let entry = lock.entry(watch.user_id.clone()).or_insert_with(Vec::new);
-            entry.push(*watch);
+            entry.push(WeatherWatch { watch: *watch, weather });
         }
 
         Ok(())
@@ -495,6 +622,11 @@ impl BotConfigs {
     //     }
     // }
 
+    /// Number of requests still waiting to be matched against users configs
+    pub fn pending() -> usize {
+        SENDER.len()
+    }
+
     pub async fn submit<I>(now: DateTime<Utc>, inputs: I, platform: Platform)
     where
         I: Iterator<Item = Request>,
@@ -542,7 +674,11 @@ impl BotConfigs {
 
                     BotConfigs::update_city_stats(&input, now.timestamp());
                 }
-                Request::Raid(_) | Request::Invasion(_) | Request::Quest(_) => {
+                Request::Raid(ref r) => {
+                    reminders::seen(r).await;
+                    BotConfigs::update_city_stats(&input, now.timestamp());
+                }
+                Request::Invasion(_) | Request::Quest(_) => {
                     BotConfigs::update_city_stats(&input, now.timestamp());
                 }
                 Request::DeviceTier(dt) => {
@@ -551,7 +687,13 @@ impl BotConfigs {
                     });
                     continue;
                 }
-                Request::Weather(_) | Request::Pokestop(_) | Request::GymDetails(_) => {}
+                Request::Weather(ref w) => {
+                    BotConfigs::update_cell(w, &now).await;
+                }
+                Request::GymDetails(ref g) => {
+                    gyms::update(g).await;
+                }
+                Request::Pokestop(_) => {}
                 _ => debug!("Unmanaged webhook: {:?}", input),
             }
 
@@ -582,7 +724,9 @@ impl BotConfigs {
     }
 
     fn update_park_stats(point: Point<f64>, pokemon_id: u16, encounter_id: String) {
+        let guard = InFlight::start();
         spawn(async move {
+            let _guard = guard;
             let city = {
                 let lock = CITIES.load();
                 lock.iter().find_map(|(id, city)| if city.coordinates.within(&point) { Some(*id) } else { None })
@@ -747,7 +891,7 @@ impl BotConfigs {
                 )
         }.await.map_err(|e| error!("MySQL query error: update device tier\n{}", e))?;
 
-        if let Some(version_chat) = &CONFIG.telegram.version_chat {
+        if let Some(version_chat) = &CONFIG.load().telegram.version_chat {
             let message = DeviceTierMessage { tier: dt };
             let image = message.get_image().await?;
             message.send(version_chat, image, "").await?;
@@ -757,6 +901,73 @@ impl BotConfigs {
     }
 }
 
+/// Renders a single notification from a json entity, writing the image to output and the caption to stdout
+pub async fn render_sample(kind: &str, input: &Path, output: &Path) -> Result<(), ()> {
+    let json =
+        tokio::fs::read_to_string(input).await.map_err(|e| error!("Cannot read sample {}: {}", input.display(), e))?;
+    let message: Box<dyn Message + Send + Sync> = match kind {
+        "pokemon" => {
+            let pokemon: rocketmap_entities::Pokemon =
+                serde_json::from_str(&json).map_err(|e| error!("Invalid pokemon sample: {}", e))?;
+            let iv = match (pokemon.individual_attack, pokemon.individual_defense, pokemon.individual_stamina) {
+                (Some(atk), Some(def), Some(sta)) => {
+                    Some(((f64::from(atk + def + sta) / 45_f64) * 100_f64).round() as u8)
+                }
+                _ => None,
+            };
+            Box::new(PokemonMessage {
+                pokemon,
+                iv,
+                distance: 0_f64,
+                direction: String::new(),
+                debug: None,
+                overlay: None,
+            })
+        }
+        "raid" => Box::new(RaidMessage {
+            raid: serde_json::from_str(&json).map_err(|e| error!("Invalid raid sample: {}", e))?,
+            distance: 0_f64,
+            debug: None,
+            reminders: None,
+            overlay: None,
+        }),
+        "lure" => Box::new(LureMessage {
+            pokestop: serde_json::from_str(&json).map_err(|e| error!("Invalid lure sample: {}", e))?,
+            debug: None,
+            overlay: None,
+        }),
+        "invasion" => Box::new(InvasionMessage {
+            invasion: serde_json::from_str(&json).map_err(|e| error!("Invalid invasion sample: {}", e))?,
+            debug: None,
+            overlay: None,
+        }),
+        "gym" => Box::new(GymMessage {
+            gym: serde_json::from_str(&json).map_err(|e| error!("Invalid gym sample: {}", e))?,
+            before: None,
+            events: Vec::new(),
+            distance: 0_f64,
+            debug: None,
+            overlay: None,
+        }),
+        _ => {
+            error!("Unknown sample kind {}", kind);
+            return Err(());
+        }
+    };
+
+    println!("{}", message.get_caption().await?);
+    match message.get_image().await? {
+        Image::Bytes(bytes) => {
+            tokio::fs::write(output, bytes).await.map_err(|e| error!("Cannot write image {}: {}", output.display(), e))
+        }
+        Image::FileUrl(url) => {
+            // images already published are only referenced by url
+            println!("{}", url);
+            Ok(())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::message::{GymMessage, InvasionMessage, Message, PokemonMessage, RaidMessage};
@@ -764,6 +975,7 @@ mod tests {
     #[tokio::test]
     async fn pokemon_image_iv() {
         tracing_subscriber::fmt::try_init().ok();
+        crate::config::init(None).unwrap();
 
         let message = PokemonMessage {
             pokemon: serde_json::from_str(
@@ -773,6 +985,7 @@ mod tests {
             distance: 0_f64,
             direction: String::new(),
             debug: None,
+            overlay: None,
         };
         message.get_image().await.unwrap();
     }
@@ -780,6 +993,7 @@ mod tests {
     #[tokio::test]
     async fn pokemon_image_no_iv() {
         tracing_subscriber::fmt::try_init().ok();
+        crate::config::init(None).unwrap();
 
         let message = PokemonMessage {
             pokemon: serde_json::from_str(
@@ -789,6 +1003,7 @@ mod tests {
             distance: 0_f64,
             direction: String::new(),
             debug: None,
+            overlay: None,
         };
         message.get_image().await.unwrap();
     }
@@ -796,6 +1011,7 @@ mod tests {
     #[tokio::test]
     async fn raid_image_egg() {
         tracing_subscriber::fmt::try_init().ok();
+        crate::config::init(None).unwrap();
 
         let message = RaidMessage {
             raid: serde_json::from_str(
@@ -803,6 +1019,8 @@ mod tests {
                 ).unwrap(),
             distance: 0_f64,
             debug: None,
+            reminders: None,
+            overlay: None,
         };
         message.get_image().await.unwrap();
     }
@@ -810,6 +1028,7 @@ mod tests {
     #[tokio::test]
     async fn raid_image_raidboss() {
         tracing_subscriber::fmt::try_init().ok();
+        crate::config::init(None).unwrap();
 
         let message = RaidMessage {
             raid: serde_json::from_str(
@@ -818,6 +1037,8 @@ mod tests {
                 ).unwrap(),
             distance: 0_f64,
             debug: None,
+            reminders: None,
+            overlay: None,
         };
         message.get_image().await.unwrap();
     }
@@ -825,12 +1046,14 @@ mod tests {
     #[tokio::test]
     async fn invasion_image() {
         tracing_subscriber::fmt::try_init().ok();
+        crate::config::init(None).unwrap();
 
         let message = InvasionMessage {
             invasion: serde_json::from_str(
                     r#"{"lure_expiration":0,"enabled":true,"updated":1564332327,"url":"http://lh6.ggpht.com/ga78DsEtufPUGu0H0oE2ZOeagwxe8aQ4k4-kBLEDdSfeFVshH8gHhQN1GMcw1OFd_n94NpwTkOa16zR5DqUL","pokestop_display":1,"longitude":11.236241,"lure_id":501,"last_modified":1564329955,"pokestop_id":"54e0ee4c0e7a42ca93d2e93ee720dc90.16","name":"Ancora Un'altra Madonnina.","incident_expire_timestamp":1564333601,"grunt_type":33,"latitude":44.723203}"#
                 ).unwrap(),
             debug: None,
+            overlay: None,
         };
         message.get_image().await.unwrap();
     }
@@ -838,13 +1061,17 @@ mod tests {
     #[tokio::test]
     async fn gym_image() {
         tracing_subscriber::fmt::try_init().ok();
+        crate::config::init(None).unwrap();
 
         let message = GymMessage {
             gym: serde_json::from_str(
                     r#"{"name":"Genova - Ponte Monumentale","latitude":44.405798,"slots_available":5,"sponsor_od":0,"team":2,"id":"e697b9b81d24466f90f97fe362a9bc02.11","url":"http://lh3.googleusercontent.com/Xg9dpwYjV3wEHSbLdsJep2duokPdQEuUt3iwcxVhajTt1OucrYU9p-nFYnn60cOP42Uf3MYn3zJ3hyiDaEPqi3-WyA","longitude":8.939554,"ex_raid_eligible":false,"in_battle":false}"#
                 ).unwrap(),
+            before: None,
+            events: Vec::new(),
             distance: 0_f64,
             debug: None,
+            overlay: None,
         };
         message.get_image().await.unwrap();
     }
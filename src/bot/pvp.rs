@@ -0,0 +1,122 @@
+use rocketmap_entities::{Pokemon, PvpRanking};
+
+use super::config::get_disguise;
+
+/// Best rank shown on captions and images
+pub const SHOWN_RANK: u16 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum League {
+    Little,
+    Great,
+    Ultra,
+    Master,
+}
+
+/// Named presets, enabled by bit in the 26th filter slot
+///
+/// Little and master league presets are reserved: the webhook doesn't report those rankings yet, so their bits are
+/// ignored while matching and a mask left without supported presets isn't checked at all
+pub const PRESETS: [(League, u16); 8] = [
+    (League::Little, 10),
+    (League::Little, 50),
+    (League::Great, 10),
+    (League::Great, 50),
+    (League::Great, 100),
+    (League::Ultra, 10),
+    (League::Ultra, 100),
+    (League::Master, 10),
+];
+
+pub const LEAGUES: [League; 4] = [League::Little, League::Great, League::Ultra, League::Master];
+
+impl League {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            League::Little => "Lega Little",
+            League::Great => "Lega Mega",
+            League::Ultra => "Lega Ultra",
+            League::Master => "Lega Master",
+        }
+    }
+
+    pub fn get_short_name(&self) -> &'static str {
+        match self {
+            League::Little => "LL",
+            League::Great => "LM",
+            League::Ultra => "LU",
+            League::Master => "LMa",
+        }
+    }
+
+    /// Webhooks only report great and ultra league rankings
+    pub fn is_supported(&self) -> bool {
+        matches!(self, League::Great | League::Ultra)
+    }
+
+    /// Rankings of every evolution, always None for unsupported leagues
+    ///
+    /// Disguised spawns are skipped, their rankings would refer to the displayed species instead of the caught one
+    pub fn get_rankings<'a>(&self, input: &'a Pokemon) -> Option<&'a [PvpRanking]> {
+        if get_disguise(input).is_some() {
+            return None;
+        }
+        match self {
+            League::Great => input.pvp_rankings_great_league.as_deref(),
+            League::Ultra => input.pvp_rankings_ultra_league.as_deref(),
+            League::Little | League::Master => None,
+        }
+    }
+
+    /// Best ranked evolution
+    pub fn get_best(&self, input: &Pokemon) -> Option<PvpRanking> {
+        self.get_rankings(input)?.iter().filter(|r| r.rank.is_some()).min_by_key(|r| r.rank).copied()
+    }
+}
+
+/// Presets enabled by the given bitmask
+pub fn get_presets(mask: u8) -> impl Iterator<Item = (League, u16)> {
+    PRESETS.into_iter().enumerate().filter_map(move |(i, p)| if mask & (1 << i) > 0 { Some(p) } else { None })
+}
+
+/// Bits of the given mask enabling presets of supported leagues
+pub fn get_supported(mask: u8) -> u8 {
+    PRESETS.iter().enumerate().filter(|(_, (league, _))| league.is_supported()).fold(0, |acc, (i, _)| acc | (1 << i))
+        & mask
+}
+
+/// First preset satisfied by the Pokémon, with the matching evolution
+///
+/// Presets of unsupported leagues are skipped, see [`get_supported`]
+pub fn check_presets(mask: u8, input: &Pokemon) -> Option<(League, u16, PvpRanking)> {
+    get_presets(get_supported(mask)).find_map(|(league, top)| match league.get_best(input) {
+        Some(rank) if rank.rank.map(|r| r <= top) == Some(true) => Some((league, top, rank)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmap_entities::Pokemon;
+
+    use super::{check_presets, get_presets, get_supported, League};
+
+    #[test]
+    fn presets() {
+        let input: Pokemon = serde_json::from_str(r#"{"pokestop_id":"3d716717cc65421490684ef9b213a382.16","disappear_time":1571079918,"cp":null,"form":0,"move_1":null,"longitude":11.241531239206385,"costume":0,"pokemon_id":656,"disappear_time_verified":false,"gender":1,"individual_attack":0,"spawnpoint_id":"None","latitude":43.771913285587665,"pokemon_level":15,"move_2":null,"individual_defense":15,"weight":null,"encounter_id":"12661125248363616471","height":null,"weather":1,"first_seen":1571078718,"individual_stamina":15,"last_modified_time":1571078718,"pvp_rankings_great_league":[{"rank":809,"percentage":0.8700643398554556,"level":40.0,"form":0,"cp":989,"pokemon":255},{"rank":351,"percentage":0.9907850542156611,"level":38.5,"pokemon":256,"form":0,"cp":1490},{"pokemon":257,"form":0,"percentage":null,"cp":null,"level":null,"rank":null}]}"#).unwrap();
+
+        assert_eq!(get_presets(0).count(), 0);
+        assert_eq!(get_presets(0b1000_0100).collect::<Vec<_>>(), vec![(League::Great, 10), (League::Master, 10)]);
+
+        assert_eq!(League::Great.get_best(&input).and_then(|r| r.rank), Some(351));
+        assert_eq!(League::Ultra.get_best(&input).map(|r| r.pokemon), None);
+
+        // great league top 100 isn't satisfied by rank 351
+        assert!(check_presets(0b0001_0000, &input).is_none());
+        assert!(check_presets(0xFF, &input).is_none());
+
+        assert_eq!(get_supported(0b0111_1100), 0b0111_1100);
+        assert_eq!(get_supported(0xFF), 0b0111_1100);
+        assert_eq!(get_supported(0b1000_0011), 0);
+    }
+}
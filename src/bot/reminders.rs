@@ -0,0 +1,363 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use tokio::{
+    spawn,
+    sync::{mpsc, Mutex},
+    time::interval,
+};
+
+use mysql_async::{from_row, params, prelude::Queryable};
+
+use chrono::Utc;
+
+use lru_time_cache::LruCache;
+
+use once_cell::sync::Lazy;
+
+use tracing::{error, info};
+
+use rocketmap_entities::Raid;
+
+use crate::db::MYSQL;
+use crate::shutdown::{self, InFlight};
+
+// last raid seen on every gym, used to reveal the boss of hatched eggs
+static RAIDS: Lazy<Mutex<LruCache<String, Raid>>> =
+    Lazy::new(|| Mutex::new(LruCache::with_expiry_duration(Duration::from_secs(7200)))); //2 hours cache
+static WHEEL: Lazy<Mutex<Wheel>> = Lazy::new(Default::default);
+// reminders are delivered through the users streams, so they share time configs and throttling
+static INBOXES: Lazy<Mutex<HashMap<String, mpsc::UnboundedSender<Delivery>>>> = Lazy::new(Default::default);
+
+type Delivery = (RaidReminder, InFlight);
+pub type Inbox = mpsc::UnboundedReceiver<Delivery>;
+
+/// Seconds to wait before checking again if the boss of an hatched egg has been scanned
+const HATCH_RETRY: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReminderKind {
+    /// some minutes before the egg hatches
+    Hatching,
+    /// the egg has hatched and the boss is known
+    Hatch,
+    /// some minutes before the raid ends
+    Ending,
+}
+
+impl ReminderKind {
+    fn get_id(&self) -> u8 {
+        match self {
+            ReminderKind::Hatching => 0,
+            ReminderKind::Hatch => 1,
+            ReminderKind::Ending => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(ReminderKind::Hatching),
+            1 => Some(ReminderKind::Hatch),
+            2 => Some(ReminderKind::Ending),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RaidReminder {
+    pub user_id: String,
+    pub kind: ReminderKind,
+    pub due: i64,
+    pub raid: Raid,
+}
+
+impl RaidReminder {
+    fn key(&self) -> (String, String, i64, ReminderKind) {
+        (self.user_id.clone(), self.raid.gym_id.clone(), self.raid.start, self.kind)
+    }
+
+    /// Next check for the boss of an hatched egg, none once the raid would be over
+    fn get_retry(&self, now: i64) -> Option<i64> {
+        Some(now + HATCH_RETRY).filter(|due| *due < self.raid.end)
+    }
+}
+
+#[derive(Default)]
+struct Wheel {
+    slots: BTreeMap<i64, Vec<RaidReminder>>,
+    keys: HashSet<(String, String, i64, ReminderKind)>,
+}
+
+impl Wheel {
+    /// Returns false if the same reminder is already scheduled
+    fn insert(&mut self, reminder: RaidReminder) -> bool {
+        if self.keys.insert(reminder.key()) {
+            self.slots.entry(reminder.due).or_default().push(reminder);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remove(&mut self, key: &(String, String, i64, ReminderKind)) -> bool {
+        if self.keys.remove(key) {
+            for slot in self.slots.values_mut() {
+                slot.retain(|r| &r.key() != key);
+            }
+            self.slots.retain(|_, slot| !slot.is_empty());
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pop_due(&mut self, now: i64) -> Vec<RaidReminder> {
+        let later = self.slots.split_off(&(now + 1));
+        let due: Vec<RaidReminder> = std::mem::replace(&mut self.slots, later).into_values().flatten().collect();
+        for reminder in due.iter() {
+            self.keys.remove(&reminder.key());
+        }
+        due
+    }
+}
+
+pub fn is_egg(raid: &Raid) -> bool {
+    !matches!(raid.pokemon_id, Some(id) if id > 0)
+}
+
+pub async fn init() -> Result<(), ()> {
+    // load persisted reminders
+    {
+        let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+        conn.query_drop("DELETE FROM bot_raid_reminders WHERE raid_end < UNIX_TIMESTAMP()")
+            .await
+            .map_err(|e| error!("MySQL delete error: {}", e))?;
+        let res = conn
+            .query_iter("SELECT user_id, kind, due, raid FROM bot_raid_reminders")
+            .await
+            .map_err(|e| error!("MySQL query error: get raid reminders\n{}", e))?;
+        let rows = res
+            .map_and_drop(from_row::<(String, u8, i64, String)>)
+            .await
+            .map_err(|e| error!("MySQL collect error: {}", e))?;
+
+        let mut lock = WHEEL.lock().await;
+        for (user_id, kind, due, raid) in rows {
+            let raid = match serde_json::from_str(&raid) {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("bot_raid_reminders.raid decoding error for user_id {}: {}", user_id, e);
+                    continue;
+                }
+            };
+            if let Some(kind) = ReminderKind::from_id(kind) {
+                lock.insert(RaidReminder { user_id, kind, due, raid });
+            }
+        }
+        info!("Loaded {} raid reminders", lock.keys.len());
+    }
+
+    spawn(async {
+        let mut interval = interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown::wait() => break,
+            }
+
+            fire(Utc::now().timestamp()).await.ok();
+        }
+    });
+
+    Ok(())
+}
+
+/// Opens the channel reminders for the given user are delivered through, replacing the previous one
+pub async fn subscribe(user_id: &str) -> Inbox {
+    let (tx, rx) = mpsc::unbounded_channel();
+    INBOXES.lock().await.insert(user_id.to_owned(), tx);
+    rx
+}
+
+/// Keeps track of the last raid seen on a gym
+pub async fn seen(raid: &Raid) {
+    let mut lock = RAIDS.lock().await;
+    lock.insert(raid.gym_id.clone(), raid.clone());
+}
+
+/// Schedules reminders for a raid just sent to a user
+/// settings are minutes before hatch, notification at hatch and minutes before end, 0 means disabled
+pub async fn schedule(user_id: &str, raid: &Raid, settings: [u8; 3]) -> Result<(), ()> {
+    let now = Utc::now().timestamp();
+    let mut reminders = Vec::new();
+    if is_egg(raid) {
+        let due = raid.start - i64::from(settings[0]) * 60;
+        if settings[0] > 0 && due > now {
+            reminders.push(RaidReminder {
+                user_id: user_id.to_owned(),
+                kind: ReminderKind::Hatching,
+                due,
+                raid: raid.clone(),
+            });
+        }
+        if settings[1] > 0 && raid.start > now {
+            reminders.push(RaidReminder {
+                user_id: user_id.to_owned(),
+                kind: ReminderKind::Hatch,
+                due: raid.start,
+                raid: raid.clone(),
+            });
+        }
+    }
+    let due = raid.end - i64::from(settings[2]) * 60;
+    if settings[2] > 0 && due > now {
+        reminders.push(RaidReminder {
+            user_id: user_id.to_owned(),
+            kind: ReminderKind::Ending,
+            due,
+            raid: raid.clone(),
+        });
+    }
+
+    let (reminders, cancelled) = {
+        let mut lock = WHEEL.lock().await;
+        // the boss has already been notified, hatch reminder is useless
+        let cancelled =
+            !is_egg(raid) && lock.remove(&(user_id.to_owned(), raid.gym_id.clone(), raid.start, ReminderKind::Hatch));
+        (reminders.into_iter().filter(|r| lock.insert(r.clone())).collect::<Vec<_>>(), cancelled)
+    };
+
+    if reminders.is_empty() && !cancelled {
+        return Ok(());
+    }
+
+    let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    if cancelled {
+        delete(
+            &mut conn,
+            &[RaidReminder {
+                user_id: user_id.to_owned(),
+                kind: ReminderKind::Hatch,
+                due: raid.start,
+                raid: raid.clone(),
+            }],
+        )
+        .await?;
+    }
+    for reminder in reminders {
+        conn.exec_drop(
+            "INSERT IGNORE INTO bot_raid_reminders (user_id, gym_id, raid_start, kind, due, raid_end, raid) VALUES (:user_id, :gym_id, :raid_start, :kind, :due, :raid_end, :raid)",
+            params! {
+                "user_id" => reminder.user_id.as_str(),
+                "gym_id" => reminder.raid.gym_id.as_str(),
+                "raid_start" => reminder.raid.start,
+                "kind" => reminder.kind.get_id(),
+                "due" => reminder.due,
+                "raid_end" => reminder.raid.end,
+                "raid" => serde_json::to_string(&reminder.raid).map_err(|e| error!("raid encoding error: {}", e))?,
+            }
+        ).await.map_err(|e| error!("MySQL insert error: insert raid reminder\n{}", e))?;
+    }
+
+    Ok(())
+}
+
+async fn delete(conn: &mut mysql_async::Conn, reminders: &[RaidReminder]) -> Result<(), ()> {
+    conn.exec_batch(
+        "DELETE FROM bot_raid_reminders WHERE user_id = :user_id AND gym_id = :gym_id AND raid_start = :raid_start AND kind = :kind",
+        reminders.iter().map(|r| {
+            params! {
+                "user_id" => r.user_id.as_str(),
+                "gym_id" => r.raid.gym_id.as_str(),
+                "raid_start" => r.raid.start,
+                "kind" => r.kind.get_id(),
+            }
+        }),
+    )
+    .await
+    .map_err(|e| error!("MySQL delete error: delete raid reminders\n{}", e))
+}
+
+async fn fire(now: i64) -> Result<(), ()> {
+    let due = WHEEL.lock().await.pop_due(now);
+    if due.is_empty() {
+        return Ok(());
+    }
+
+    let mut done = Vec::new();
+    for mut reminder in due {
+        let last_seen = {
+            let mut lock = RAIDS.lock().await;
+            lock.get(&reminder.raid.gym_id).filter(|r| r.start == reminder.raid.start).cloned()
+        };
+
+        match (reminder.kind, last_seen) {
+            (ReminderKind::Hatch, Some(raid)) if !is_egg(&raid) => reminder.raid = raid,
+            (ReminderKind::Hatch, _) => {
+                // boss not scanned yet, try again later
+                if let Some(due) = reminder.get_retry(now) {
+                    reminder.due = due;
+                    WHEEL.lock().await.insert(reminder);
+                } else {
+                    done.push(reminder);
+                }
+                continue;
+            }
+            (_, Some(raid)) => reminder.raid = raid,
+            (_, None) => {}
+        }
+
+        // users who disabled the bot in the meantime don't get reminders
+        {
+            let mut lock = INBOXES.lock().await;
+            if let Some(tx) = lock.get(&reminder.user_id) {
+                if tx.send((reminder.clone(), InFlight::start())).is_err() {
+                    lock.remove(&reminder.user_id);
+                }
+            }
+        }
+        done.push(reminder);
+    }
+
+    if !done.is_empty() {
+        let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+        delete(&mut conn, &done).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RaidReminder, ReminderKind, Wheel};
+
+    #[test]
+    fn wheel() {
+        let raid: rocketmap_entities::Raid = serde_json::from_str(
+                r#"{"gym_name":"Cesena - Stadio Dino Manuzzi","gym_url":"http://lh3.googleusercontent.com/EDFoOOm1G0hhjtBdJmBUjcUAcfB1se0zPatRviNuHcsK2oxwIokSONXWb8CUAJYYHI0Cl32dWiVlIMXVZe_X","end":1564338495,"form":0,"is_exclusive":false,"longitude":12.413359,"cp":0,"team_id":1,"pokemon_id":0,"gym_id":"fe20dd37398341a4b83751c5c050aaec.16","move_2":0,"level":4,"move_1":0,"start":1564335795,"ex_raid_eligible":false,"spawn":1564332195,"latitude":45.567758,"gender":0}"#
+            ).unwrap();
+
+        let mut wheel = Wheel::default();
+        let hatch = RaidReminder { user_id: String::from("1"), kind: ReminderKind::Hatch, due: 20, raid: raid.clone() };
+        assert!(wheel.insert(hatch.clone()));
+        // deduplicated per user and gym
+        assert!(!wheel.insert(RaidReminder { due: 30, ..hatch.clone() }));
+        assert!(wheel.insert(RaidReminder { user_id: String::from("2"), ..hatch.clone() }));
+        assert!(wheel.insert(RaidReminder { kind: ReminderKind::Ending, due: 40, ..hatch.clone() }));
+
+        assert!(wheel.pop_due(10).is_empty());
+        assert_eq!(wheel.pop_due(20).len(), 2);
+        assert!(wheel.remove(&RaidReminder { kind: ReminderKind::Ending, ..hatch.clone() }.key()));
+        assert!(wheel.pop_due(50).is_empty());
+
+        // can be scheduled again once fired
+        assert!(wheel.insert(hatch.clone()));
+
+        // hatch checks stop with the raid
+        assert_eq!(hatch.get_retry(raid.end - 120), Some(raid.end - 60));
+        assert_eq!(hatch.get_retry(raid.end - 60), None);
+    }
+}
@@ -2,7 +2,6 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
-    time::Duration,
 };
 
 use futures_util::stream::{self, Stream, StreamExt};
@@ -11,22 +10,26 @@ use tracing::error;
 
 use once_cell::sync::Lazy;
 
-use stream_throttle::{ThrottlePool, ThrottleRate, ThrottledStream};
-
 use tokio::sync::mpsc;
 
-pub type Message = (String, Box<dyn super::message::Message + Send + Sync>, String);
+use crate::config::CONFIG;
+use crate::shutdown::InFlight;
+
+use super::throttle::Throttled;
+
+// we can send globally only 30 telegram messages per second
+const DEFAULT_GLOBAL_RATE: u32 = 30;
+
+pub type Message = (String, Box<dyn super::message::Message + Send + Sync>, String, InFlight);
 
 static TX: Lazy<mpsc::UnboundedSender<Box<dyn Stream<Item = Message> + Send + Unpin>>> = Lazy::new(|| {
     let (tx, rx) = mpsc::unbounded_channel();
     tokio::spawn(async move {
         let stream = SelectAll::new(rx);
-        // we can send globally only 30 telegram messages per second
-        let rate = ThrottleRate::new(30, Duration::from_secs(1));
-        let pool = ThrottlePool::new(rate);
-        stream
-            .throttle(pool)
-            .for_each_concurrent(None, |(user_id, message, map_type): Message| async move {
+        Throttled::new(stream, global_rate)
+            .for_each_concurrent(None, |(user_id, message, map_type, guard): Message| async move {
+                // keep the guard alive until the message has been sent
+                let _guard = guard;
                 if let Ok(img) = message.get_image().await {
                     message.send(&user_id, img, &map_type).await.ok();
                 }
@@ -36,6 +39,10 @@ static TX: Lazy<mpsc::UnboundedSender<Box<dyn Stream<Item = Message> + Send + Un
     tx
 });
 
+fn global_rate() -> u32 {
+    CONFIG.load().telegram.global_rate.unwrap_or(DEFAULT_GLOBAL_RATE)
+}
+
 pub async fn add<S>(stream: S) -> Result<(), ()>
 where
     S: Stream<Item = Message> + Send + Unpin + 'static,
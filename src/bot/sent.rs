@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use tokio::{spawn, time::interval};
+
+use mysql_async::{from_row, params, prelude::Queryable};
+
+use chrono::Utc;
+
+use tracing::{debug, error, info};
+
+use rocketmap_entities::Raid;
+
+use super::{reminders::is_egg, BOT_CONFIGS};
+
+use crate::config::CONFIG;
+use crate::db::MYSQL;
+use crate::shutdown;
+use crate::telegram::delete_message;
+
+/// Seconds between checks for expired messages
+const DELETE_INTERVAL: u64 = 60;
+
+/// Remembers the message sent for an egg, so it can be updated in place when the boss is revealed
+pub async fn add_egg(chat_id: &str, raid: &Raid, message_id: i64) -> Result<(), ()> {
+    if !is_egg(raid) {
+        return Ok(());
+    }
+
+    let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    conn.exec_drop(
+        "INSERT INTO bot_sent_messages (user_id, message_id, gym_id, raid_start, raid_end) VALUES (:user_id, :message_id, :gym_id, :raid_start, :raid_end) ON DUPLICATE KEY UPDATE gym_id = VALUES(gym_id), raid_start = VALUES(raid_start), raid_end = VALUES(raid_end)",
+        params! {
+            "user_id" => chat_id,
+            "message_id" => message_id,
+            "gym_id" => raid.gym_id.as_str(),
+            "raid_start" => raid.start,
+            "raid_end" => raid.end,
+        },
+    )
+    .await
+    .map_err(|e| error!("MySQL query error: insert sent egg\n{}", e))
+}
+
+/// Returns the egg message to be replaced by the given raid boss, if any
+pub async fn take_egg(chat_id: &str, raid: &Raid) -> Option<i64> {
+    if is_egg(raid) {
+        return None;
+    }
+
+    let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e)).ok()?;
+    let message_id: i64 = conn
+        .exec_first(
+            "SELECT message_id FROM bot_sent_messages WHERE user_id = :user_id AND gym_id = :gym_id AND raid_start = :raid_start",
+            params! {
+                "user_id" => chat_id,
+                "gym_id" => raid.gym_id.as_str(),
+                "raid_start" => raid.start,
+            },
+        )
+        .await
+        .map_err(|e| error!("MySQL query error: get sent egg\n{}", e))
+        .ok()??;
+
+    // an egg is replaced only once, rows kept just for it are dropped
+    conn.exec_drop(
+        "DELETE FROM bot_sent_messages WHERE user_id = :user_id AND message_id = :message_id AND expire IS NULL",
+        params! {
+            "user_id" => chat_id,
+            "message_id" => message_id,
+        },
+    )
+    .await
+    .map_err(|e| error!("MySQL delete error: delete sent egg\n{}", e))
+    .ok()?;
+    conn.exec_drop(
+        "UPDATE bot_sent_messages SET gym_id = NULL, raid_start = NULL, raid_end = NULL WHERE user_id = :user_id AND message_id = :message_id",
+        params! {
+            "user_id" => chat_id,
+            "message_id" => message_id,
+        },
+    )
+    .await
+    .map_err(|e| error!("MySQL query error: update sent egg\n{}", e))
+    .ok()?;
+
+    Some(message_id)
+}
+
+/// Schedules the deletion of a sent message, if the user enabled it
+pub async fn add_expire(chat_id: &str, message_id: i64, expire: i64) -> Result<(), ()> {
+    let enabled = {
+        let lock = BOT_CONFIGS.read().await;
+        lock.get(chat_id).and_then(|c| c.more.d).unwrap_or_default() > 0
+    };
+    if !enabled {
+        return Ok(());
+    }
+
+    let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    conn.exec_drop(
+        "INSERT INTO bot_sent_messages (user_id, message_id, expire) VALUES (:user_id, :message_id, :expire) ON DUPLICATE KEY UPDATE expire = VALUES(expire)",
+        params! {
+            "user_id" => chat_id,
+            "message_id" => message_id,
+            "expire" => expire,
+        },
+    )
+    .await
+    .map_err(|e| error!("MySQL query error: insert sent message\n{}", e))
+}
+
+pub fn init() {
+    spawn(async {
+        let mut interval = interval(Duration::from_secs(DELETE_INTERVAL));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown::wait() => break,
+            }
+
+            delete_expired(Utc::now().timestamp()).await.ok();
+        }
+    });
+}
+
+async fn delete_expired(now: i64) -> Result<(), ()> {
+    let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    // eggs of raids already over can't be replaced anymore
+    conn.exec_drop(
+        "DELETE FROM bot_sent_messages WHERE expire IS NULL AND raid_end < :now",
+        params! {
+            "now" => now,
+        },
+    )
+    .await
+    .map_err(|e| error!("MySQL delete error: delete old eggs\n{}", e))?;
+
+    let res = conn
+        .exec_iter(
+            "SELECT user_id, message_id FROM bot_sent_messages WHERE expire <= :now",
+            params! {
+                "now" => now,
+            },
+        )
+        .await
+        .map_err(|e| error!("MySQL query error: get expired messages\n{}", e))?;
+    let rows = res.map_and_drop(from_row::<(String, i64)>).await.map_err(|e| error!("MySQL collect error: {}", e))?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let config = CONFIG.load_full();
+    for (user_id, message_id) in rows.iter() {
+        // messages already deleted by the user, or too old to be deleted, are simply forgotten
+        if delete_message(&config.telegram.bot_token, user_id, *message_id).send().await.is_err() {
+            debug!("Cannot delete message {} from chat {}", message_id, user_id);
+        }
+    }
+
+    conn.exec_batch(
+        "DELETE FROM bot_sent_messages WHERE user_id = :user_id AND message_id = :message_id",
+        rows.iter().map(|(user_id, message_id)| {
+            params! {
+                "user_id" => user_id,
+                "message_id" => message_id,
+            }
+        }),
+    )
+    .await
+    .map_err(|e| error!("MySQL delete error: {}", e))?;
+    info!("Deleted {} expired messages", rows.len());
+
+    Ok(())
+}
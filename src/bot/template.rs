@@ -0,0 +1,250 @@
+use std::collections::{hash_map::DefaultHasher, BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use chrono::Utc;
+
+use serde::Deserialize;
+
+use tokio::fs;
+
+use tracing::error;
+
+use super::message::{get_text_width, open_font, open_image, truncate_str};
+use super::theme::get_active;
+
+use crate::config::CONFIG;
+
+/// Values available to templates as {name} placeholders
+pub type Values = BTreeMap<&'static str, String>;
+
+/// Image layout loaded from <sender>/templates/<kind>.toml, replacing the builtin one
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    /// background image, relative to the sender folder
+    background: String,
+    /// top left corner of the map
+    map: Option<[u32; 2]>,
+    #[serde(default)]
+    layers: Vec<Layer>,
+    #[serde(default)]
+    texts: Vec<Text>,
+    /// hash of the source, to tell apart images rendered by different versions
+    #[serde(skip)]
+    version: u64,
+}
+
+/// Image drawn over the background, relative to the assets folder
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    path: String,
+    x: u32,
+    y: u32,
+    /// resize to width and height
+    size: Option<[u32; 2]>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Anchor {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Text {
+    text: String,
+    /// font file, relative to the sender folder
+    font: String,
+    size: f32,
+    x: u32,
+    y: u32,
+    /// which side of the text is placed on x
+    #[serde(default)]
+    anchor: Anchor,
+    #[serde(default)]
+    color: [u8; 4],
+    /// longer texts are truncated
+    max_len: Option<usize>,
+}
+
+/// Replaces {name} placeholders, None when any of them has no value
+fn fill(pattern: &str, values: &Values) -> Option<String> {
+    let mut res = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        res.push_str(&rest[..start]);
+        res.push_str(values.get(&rest[(start + 1)..end]).filter(|v| !v.is_empty())?);
+        rest = &rest[(end + 1)..];
+    }
+    res.push_str(rest);
+    Some(res)
+}
+
+impl Template {
+    /// Template for the given message kind, None when missing or invalid so the builtin layout is used
+    ///
+    /// The active theme can provide its own version in templates/<theme>/<kind>.toml
+    pub async fn load(kind: &str) -> Option<Self> {
+        let sender = CONFIG.load().images.sender.clone();
+        let mut paths = vec![format!("{}templates/{}.toml", sender, kind)];
+        if let Some(theme) = get_active(&Utc::now()) {
+            paths.insert(0, format!("{}templates/{}/{}.toml", sender, theme.name, kind));
+        }
+
+        let mut found = None;
+        for path in paths {
+            match fs::read_to_string(&path).await {
+                Ok(s) => {
+                    found = Some((path, s));
+                    break;
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => {
+                    error!("error reading template {}: {}", path, e);
+                    return None;
+                }
+            }
+        }
+        let (path, source) = found?;
+
+        let mut template: Template =
+            toml::from_str(&source).map_err(|e| error!("error parsing template {}: {}", path, e)).ok()?;
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        template.version = hasher.finish();
+        Some(template)
+    }
+
+    /// Unique name of the image rendered with the given values
+    pub fn get_hash(&self, values: &Values) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.version.hash(&mut hasher);
+        values.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub async fn render(&self, values: &Values, map: &image::DynamicImage) -> Result<image::DynamicImage, ()> {
+        let config = CONFIG.load_full();
+
+        let mut background = {
+            let path =
+                fill(&self.background, values).ok_or_else(|| error!("missing values for template background"))?;
+            open_image(&PathBuf::from(format!("{}{}", config.images.sender, path))).await?
+        };
+
+        if let Some([x, y]) = self.map {
+            image::imageops::overlay(&mut background, map, x, y);
+        }
+
+        // layers without values or files are skipped, this way optional elements can be described
+        for layer in &self.layers {
+            let path = match fill(&layer.path, values) {
+                Some(p) => PathBuf::from(format!("{}{}", config.images.assets, p)),
+                None => continue,
+            };
+            let image = match open_image(&path).await {
+                Ok(i) => i,
+                Err(_) => continue,
+            };
+            let image = match layer.size {
+                Some([width, height]) => image::DynamicImage::ImageRgba8(image::imageops::resize(
+                    &image,
+                    width,
+                    height,
+                    image::imageops::FilterType::Triangle,
+                )),
+                None => image,
+            };
+            image::imageops::overlay(&mut background, &image, layer.x, layer.y);
+        }
+
+        let mut fonts = HashMap::new();
+        for text in &self.texts {
+            let s = match fill(&text.text, values) {
+                Some(s) => match text.max_len {
+                    Some(limit) => truncate_str(&s, limit, '-'),
+                    None => s,
+                },
+                None => continue,
+            };
+            if !fonts.contains_key(&text.font) {
+                let font = open_font(&format!("{}{}", config.images.sender, text.font)).await?;
+                fonts.insert(text.font.clone(), font);
+            }
+            let font = &fonts[&text.font];
+            let scale = rusttype::Scale::uniform(text.size);
+            let x = match text.anchor {
+                Anchor::Left => text.x as i32,
+                Anchor::Center => text.x as i32 - get_text_width(font, scale, &s) / 2,
+                Anchor::Right => text.x as i32 - get_text_width(font, scale, &s),
+            };
+            imageproc::drawing::draw_text_mut(
+                &mut background,
+                image::Rgba::<u8>(text.color),
+                x.max(0) as u32,
+                text.y,
+                scale,
+                font,
+                &s,
+            );
+        }
+
+        Ok(background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fill, Template, Values};
+
+    #[test]
+    fn placeholders() {
+        let mut values = Values::new();
+        values.insert("name", String::from("Dratini"));
+        values.insert("iv", String::from("100"));
+        values.insert("form", String::new());
+
+        assert_eq!(fill("{name} {iv}%", &values).as_deref(), Some("Dratini 100%"));
+        assert_eq!(fill("img/{name}", &values).as_deref(), Some("img/Dratini"));
+        assert_eq!(fill("no placeholders", &values).as_deref(), Some("no placeholders"));
+        // empty or missing values skip the element
+        assert_eq!(fill("{name} ({form})", &values), None);
+        assert_eq!(fill("{cp}", &values), None);
+        assert_eq!(fill("{name", &values), None);
+    }
+
+    #[test]
+    fn parse() {
+        let template: Template = toml::from_str(
+            r#"
+            background = "images/msg-bgs/msg-raid-sm-t{team}.png"
+            map = [0, 83]
+
+            [[layers]]
+            path = "img/pkmns/gym_images/t{team}m{members}p{ex}.png"
+            x = 4
+            y = 11
+
+            [[texts]]
+            text = "{name}"
+            font = "fonts/calibrib.ttf"
+            size = 17
+            x = 63
+            y = 35
+            max_len = 30
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(template.layers.len(), 1);
+        assert_eq!(template.texts[0].max_len, Some(30));
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+
+use chrono_tz::Europe::Rome;
+
+use crate::config::{Theme, CONFIG};
+
+/// First theme active on the given day
+pub fn get_active(now: &DateTime<Utc>) -> Option<Theme> {
+    let day = now.with_timezone(&Rome).format("%m%d").to_string().parse().ok()?;
+    CONFIG.load().themes.iter().find(|t| t.is_active(day)).cloned()
+}
+
+/// Caption icon of the given message kind, if overridden by the active theme
+pub fn get_icon(kind: &str) -> Option<String> {
+    get_active(&Utc::now())?.icons.get(kind).cloned()
+}
+
+/// Text appended to captions by the active theme
+pub fn get_footer() -> Option<String> {
+    get_active(&Utc::now())?.footer
+}
+
+/// Background image path, taken from the active theme folder when it provides one
+pub fn get_background(name: &str) -> PathBuf {
+    let sender = CONFIG.load().images.sender.clone();
+    if let Some(dir) = get_active(&Utc::now()).and_then(|t| t.backgrounds) {
+        let path = PathBuf::from(format!("{}{}{}", sender, dir, name));
+        if path.exists() {
+            return path;
+        }
+    }
+    PathBuf::from(format!("{}images/msg-bgs/{}", sender, name))
+}
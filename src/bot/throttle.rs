@@ -0,0 +1,47 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::stream::{Stream, StreamExt};
+
+use tokio::time::{sleep, Sleep};
+
+/// Stream adapter that spaces out items, the rate is read again after every item so config changes apply live
+pub struct Throttled<S> {
+    inner: S,
+    rate: fn() -> u32,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> Throttled<S> {
+    pub fn new(inner: S, rate: fn() -> u32) -> Self {
+        Throttled { inner, rate, delay: None }
+    }
+}
+
+impl<S> Stream for Throttled<S>
+where
+    S: Stream + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(delay) = this.delay.as_mut() {
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.delay = None;
+        }
+
+        let res = this.inner.poll_next_unpin(cx);
+        if let Poll::Ready(Some(_)) = res {
+            let rate = (this.rate)().max(1);
+            this.delay = Some(Box::pin(sleep(Duration::from_secs(1) / rate)));
+        }
+        res
+    }
+}
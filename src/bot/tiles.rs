@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use once_cell::sync::Lazy;
+
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+
+use tracing::{debug, error};
+
+use crate::config::CONFIG;
+
+/// start of the current second and requests made in it, for every rate limited provider
+static WINDOWS: Lazy<Mutex<HashMap<String, (Instant, u32)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// opened MBTiles file, reopened when the config changes
+static MBTILES: Lazy<std::sync::Mutex<Option<(String, Connection)>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Counts a request in the given window, false when the rate is already reached
+fn take(window: &mut (Instant, u32), rate: u32, now: Instant) -> bool {
+    if now.duration_since(window.0) >= Duration::from_secs(1) {
+        *window = (now, 0);
+    }
+    if window.1 >= rate {
+        return false;
+    }
+    window.1 += 1;
+    true
+}
+
+/// MBTiles rows follow the TMS scheme, counted from the bottom
+fn tms_row(z: u8, y: i64) -> i64 {
+    (1_i64 << z) - 1 - y
+}
+
+/// Tile from the MBTiles file, then the disk cache and at last from the first available provider
+pub async fn get_tile(z: u8, x: i64, y: i64) -> Result<image::DynamicImage, ()> {
+    let bytes = get_bytes(z, x, y).await?;
+    image::load_from_memory(&bytes).map_err(|e| error!("error loading tile {}/{}/{}: {}", z, x, y, e))
+}
+
+async fn get_bytes(z: u8, x: i64, y: i64) -> Result<Vec<u8>, ()> {
+    let config = CONFIG.load_full();
+
+    if let Some(path) = &config.osm.mbtiles {
+        if let Some(bytes) = read_mbtiles(path.clone(), z, x, y).await? {
+            return Ok(bytes);
+        }
+    }
+
+    let cache = config.osm.cache.as_ref().map(|c| (PathBuf::from(format!("{}{}/{}/{}.tile", c.path, z, x, y)), c.ttl));
+    if let Some((path, ttl)) = &cache {
+        if let Some(bytes) = read_cache(path, *ttl).await {
+            return Ok(bytes);
+        }
+    }
+
+    for (url, rate) in config.osm.get_providers() {
+        if let Some(rate) = rate {
+            let mut lock = WINDOWS.lock().await;
+            let window = lock.entry(url.to_owned()).or_insert_with(|| (Instant::now(), 0));
+            if !take(window, rate, Instant::now()) {
+                debug!("tile provider {} rate limited", url);
+                continue;
+            }
+        }
+
+        if let Ok(bytes) = download(url, z, x, y, config.osm.timeout).await {
+            if let Some((path, _)) = &cache {
+                write_cache(path, &bytes).await.ok();
+            }
+            return Ok(bytes);
+        }
+    }
+
+    error!("tile {}/{}/{} not available from any source", z, x, y);
+    Err(())
+}
+
+async fn read_mbtiles(path: String, z: u8, x: i64, y: i64) -> Result<Option<Vec<u8>>, ()> {
+    tokio::task::spawn_blocking(move || {
+        let mut lock = MBTILES.lock().map_err(|e| error!("MBTiles lock error: {}", e))?;
+        if !matches!(lock.as_ref(), Some((p, _)) if *p == path) {
+            let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| error!("error opening MBTiles {}: {}", path, e))?;
+            *lock = Some((path.clone(), conn));
+        }
+
+        let (_, conn) = lock.as_ref().ok_or_else(|| error!("MBTiles {} not opened", path))?;
+        conn.query_row(
+            "SELECT tile_data FROM tiles WHERE zoom_level = ?1 AND tile_column = ?2 AND tile_row = ?3",
+            params![z, x, tms_row(z, y)],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| error!("error reading tile {}/{}/{} from MBTiles {}: {}", z, x, y, path, e))
+    })
+    .await
+    .map_err(|e| error!("MBTiles task error: {}", e))?
+}
+
+/// Cached tile, if not older than ttl seconds
+async fn read_cache(path: &Path, ttl: u64) -> Option<Vec<u8>> {
+    let meta = match fs::metadata(path).await {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => {
+            error!("error reading tile {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let age = meta.modified().ok().and_then(|m| SystemTime::now().duration_since(m).ok())?;
+    if age > Duration::from_secs(ttl) {
+        return None;
+    }
+
+    fs::read(path).await.map_err(|e| error!("error reading tile {}: {}", path.display(), e)).ok()
+}
+
+async fn write_cache(path: &Path, bytes: &[u8]) -> Result<(), ()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await.map_err(|e| error!("error creating tile dir {}: {}", dir.display(), e))?;
+    }
+    let mut file = fs::File::create(path).await.map_err(|e| error!("error creating tile {}: {}", path.display(), e))?;
+    file.write_all(bytes).await.map_err(|e| error!("error writing tile {}: {}", path.display(), e))
+}
+
+async fn download(tile_url: &str, z: u8, x: i64, y: i64, timeout: Option<u64>) -> Result<Vec<u8>, ()> {
+    let tile_url = tile_url
+        .replace("{s}", {
+            use rand::Rng;
+            match rand::thread_rng().gen_range(0..3) {
+                0 => "a",
+                1 => "b",
+                _ => "c",
+            }
+        })
+        .replace("{z}", &z.to_string())
+        .replace("{x}", &x.to_string())
+        .replace("{y}", &y.to_string());
+    let url = reqwest::Url::parse(&tile_url).map_err(|e| error!("error building tile url: {}", e))?;
+
+    let req = reqwest::Client::new().get(url);
+    let res = if let Some(t) = timeout { req.timeout(Duration::from_secs(t)) } else { req }
+        .send()
+        .await
+        .map_err(|e| error!("error retrieving tile {}: {}", tile_url, e))?;
+
+    if !res.status().is_success() {
+        error!("tile {} retriever failed with status code {}", tile_url, res.status());
+        Err(())
+    } else {
+        let bytes = res.bytes().await.map_err(|e| error!("error reading tile {}: {}", tile_url, e))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{take, tms_row};
+
+    #[test]
+    fn rate_limit() {
+        let now = Instant::now();
+        let mut window = (now, 0);
+
+        assert!(take(&mut window, 2, now));
+        assert!(take(&mut window, 2, now));
+        assert!(!take(&mut window, 2, now + Duration::from_millis(500)));
+        // a new second resets the count
+        assert!(take(&mut window, 2, now + Duration::from_secs(1)));
+        assert_eq!(window.1, 1);
+    }
+
+    #[test]
+    fn mbtiles_rows() {
+        assert_eq!(tms_row(0, 0), 0);
+        assert_eq!(tms_row(14, 0), 16383);
+        assert_eq!(tms_row(14, 5900), 10483);
+    }
+}
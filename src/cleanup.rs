@@ -1,4 +1,4 @@
-use std::time;
+use std::{path::PathBuf, time};
 
 use chrono::{Duration, Utc};
 
@@ -14,7 +14,7 @@ use tracing::error;
 use crate::config::CONFIG;
 
 async fn cleanup() -> Result<(), ()> {
-    let mut dir = read_dir(format!("{}img_sent/", CONFIG.images.bot,))
+    let mut dir = read_dir(format!("{}img_sent/", CONFIG.load().images.bot,))
         .await
         .map_err(|e| error!("cleanup error: can't open dir {}", e))?;
 
@@ -30,13 +30,57 @@ async fn cleanup() -> Result<(), ()> {
     Ok(())
 }
 
+/// Removes expired tiles, then the oldest ones until the cache fits its size
+async fn cleanup_tiles() -> Result<(), ()> {
+    let config = CONFIG.load_full();
+    let cache = match &config.osm.cache {
+        Some(cache) => cache,
+        None => return Ok(()),
+    };
+
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::from(&cache.path)];
+    while let Some(path) = dirs.pop() {
+        let mut dir = read_dir(&path).await.map_err(|e| error!("cleanup error: can't open dir {}", e))?;
+        while let Ok(Some(file)) = dir.next_entry().await.map_err(|e| error!("cleanup error: can't read dir {}", e)) {
+            match file.metadata().await {
+                Ok(meta) if meta.is_dir() => dirs.push(file.path()),
+                Ok(meta) => files.push((meta.modified().unwrap_or(time::UNIX_EPOCH), meta.len(), file.path())),
+                Err(e) => error!("cleanup error: can't read file {}", e),
+            }
+        }
+    }
+
+    // newest first, so the size budget keeps them
+    files.sort_unstable_by_key(|(modified, ..)| std::cmp::Reverse(*modified));
+    let now = time::SystemTime::now();
+    let ttl = time::Duration::from_secs(cache.ttl);
+    let mut total = 0;
+    for (modified, size, path) in files {
+        let expired = now.duration_since(modified).map(|age| age > ttl).unwrap_or(false);
+        if expired || cache.max_size.map(|max| total + size > max).unwrap_or(false) {
+            if let Err(e) = remove_file(&path).await {
+                error!("cleanup error: can't remove file {}", e);
+            }
+        } else {
+            total += size;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn init() {
     tokio::spawn(async {
         let mut interval = interval(time::Duration::from_secs(3600));
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = crate::shutdown::wait() => break,
+            }
 
             cleanup().await.ok();
+            cleanup_tiles().await.ok();
         }
     });
 }
@@ -0,0 +1,125 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::Platform;
+
+pub const USAGE: &str = "Usage: pokifications [--config <file>] [command]
+
+Commands:
+    serve                                   start the webhook server (default)
+    check-config                            validate the configuration and exit
+    replay [--platform <rdm|mad>] <file>    process a webhook payload saved on file
+    render-sample <kind> <input> <output>   render a notification image from a json file
+                                            kind is one of pokemon, raid, lure, invasion, gym
+    migrate                                 create or upgrade the database schema
+
+Options:
+    -c, --config <file>    config file, defaults to POKIFICATIONS_CONFIG or <executable>.toml
+    -h, --help             print this message
+
+Every config key can be overridden with a POKIFICATIONS_<SECTION>_<KEY> environment variable,
+e.g. POKIFICATIONS_TELEGRAM_BOT_TOKEN, nested keys and array items are joined by a double underscore,
+e.g. POKIFICATIONS_OSM_CACHE__TTL or POKIFICATIONS_THEMES__0__FOOTER, arrays can be set as toml values";
+
+pub enum Command {
+    Serve,
+    CheckConfig,
+    Replay { file: PathBuf, platform: Platform },
+    RenderSample { kind: String, input: PathBuf, output: PathBuf },
+    Migrate,
+    Help,
+}
+
+pub struct Args {
+    pub config: Option<PathBuf>,
+    pub command: Command,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(env::args().skip(1))
+    }
+
+    fn parse_from<I>(args: I) -> Result<Self, String>
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut config = None;
+        let mut platform = Platform::Unknown;
+        let mut positional = Vec::new();
+
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" | "--config" => {
+                    config = Some(args.next().ok_or_else(|| format!("Missing value for {}", arg))?.into());
+                }
+                "-p" | "--platform" => {
+                    platform = match args.next().as_deref() {
+                        Some("rdm") => Platform::Rdm,
+                        Some("mad") => Platform::Mad,
+                        Some(p) => return Err(format!("Unknown platform {}", p)),
+                        None => return Err(format!("Missing value for {}", arg)),
+                    };
+                }
+                "-h" | "--help" => {
+                    return Ok(Args { config, command: Command::Help });
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let command = match positional.next().as_deref() {
+            None | Some("serve") => Command::Serve,
+            Some("check-config") => Command::CheckConfig,
+            Some("replay") => {
+                Command::Replay { file: positional.next().ok_or("Missing payload file for replay")?.into(), platform }
+            }
+            Some("render-sample") => Command::RenderSample {
+                kind: positional.next().ok_or("Missing kind for render-sample")?,
+                input: positional.next().ok_or("Missing input file for render-sample")?.into(),
+                output: positional.next().ok_or("Missing output file for render-sample")?.into(),
+            },
+            Some("migrate") => Command::Migrate,
+            // backward compatibility: the config file used to be the only argument
+            Some(path) if config.is_none() && Path::new(path).is_file() => {
+                config = Some(path.into());
+                Command::Serve
+            }
+            Some(c) => return Err(format!("Unknown command {}", c)),
+        };
+
+        if let Some(arg) = positional.next() {
+            return Err(format!("Unexpected argument {}", arg));
+        }
+
+        Ok(Args { config, command })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Args, Command};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse_from(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn commands() {
+        assert!(matches!(parse(&[]).unwrap().command, Command::Serve));
+        assert!(matches!(parse(&["check-config"]).unwrap().command, Command::CheckConfig));
+        assert!(matches!(parse(&["migrate"]).unwrap().command, Command::Migrate));
+
+        let args = parse(&["-c", "test.toml", "replay", "--platform", "mad", "payload.json"]).unwrap();
+        assert_eq!(args.config.unwrap().to_str(), Some("test.toml"));
+        assert!(matches!(args.command, Command::Replay { .. }));
+
+        assert!(parse(&["replay"]).is_err());
+        assert!(parse(&["render-sample", "pokemon", "input.json"]).is_err());
+        assert!(parse(&["unknown"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+    }
+}
@@ -1,15 +1,51 @@
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::Read;
+use std::fs;
+use std::io::ErrorKind;
 use std::num::NonZeroUsize;
-#[cfg(test)]
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use arc_swap::{ArcSwap, Guard};
+
+use once_cell::sync::OnceCell;
+
+use serde_path_to_error::Segment;
+
+use toml::{Table, Value};
+
+use tracing::{debug, error, info, warn};
+
+const ENV_PREFIX: &str = "POKIFICATIONS_";
+const PATH_VAR: &str = "POKIFICATIONS_CONFIG";
+
+pub static CONFIG: Current = Current(OnceCell::new());
+static PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Running configuration, set only by init and replaced by reload
+pub struct Current(OnceCell<ArcSwap<Config>>);
+
+impl Current {
+    fn get(&self) -> &ArcSwap<Config> {
+        // main calls init before starting anything else
+        self.0.get().expect("config::init must be called first")
+    }
 
-use serde::Deserialize;
+    pub fn load(&self) -> Guard<Arc<Config>> {
+        self.get().load()
+    }
 
-use once_cell::sync::Lazy;
+    pub fn load_full(&self) -> Arc<Config> {
+        self.get().load_full()
+    }
 
-pub static CONFIG: Lazy<Config> = Lazy::new(Config::new);
+    fn store(&self, config: Config) {
+        let config = Arc::new(config);
+        self.0.get_or_init(|| ArcSwap::new(config.clone())).store(config);
+    }
+}
 
 #[derive(Deserialize)]
 pub struct Config {
@@ -18,6 +54,11 @@ pub struct Config {
     pub images: Images,
     pub osm: Osm,
     pub telegram: Telegram,
+    #[serde(default)]
+    pub lists: Lists,
+    /// seasonal themes, the first one active is applied
+    #[serde(default = "default_themes")]
+    pub themes: Vec<Theme>,
 }
 
 #[derive(Deserialize)]
@@ -27,8 +68,10 @@ pub struct Service {
     pub safeword: Option<String>,
     pub rdm_safeword: Option<String>,
     pub mad_safeword: Option<String>,
+    pub admin_safeword: Option<String>,
     pub queue_size: usize,
     pub lru_size: NonZeroUsize,
+    pub shutdown_timeout: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -36,19 +79,96 @@ pub struct Database {
     pub url: String,
 }
 
+/// External data sources, file paths or urls
+#[derive(Default, Deserialize)]
+pub struct Lists {
+    /// game master json, for base heights and weights, defaults to lists::GAMEMASTER_URL
+    pub gamemaster: Option<String>,
+    /// current grunt lineups and rewards, defaults to lists::GRUNTS_URL
+    pub grunts: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct Images {
     pub bot: String,
     pub bot_pub: Option<String>,
     pub sender: String,
     pub assets: String,
+    /// encoding of notification images
+    #[serde(default)]
+    pub format: ImageFormat,
+    /// JPEG quality, from 1 to 100
+    pub quality: Option<u8>,
+    /// bytes budget of every notification image, JPEG quality is lowered to meet it
+    pub max_size: Option<usize>,
+}
+
+/// Encoding of notification images
+///
+/// WebP is left out on purpose: image 0.23 can't encode it, the webp crate would add libwebp as a native build
+/// dependency, and Telegram recompresses uploaded photos anyway, so it would only save some upload bytes
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+
+    pub fn get_mime(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+        }
+    }
 }
 
 #[derive(Deserialize)]
-#[allow(dead_code)]
 pub struct Osm {
-    pub tile_url: String,
+    /// first tile provider, without rate limit
+    pub tile_url: Option<String>,
     pub timeout: Option<u64>,
+    /// further tile providers, tried in order when the previous ones fail or are rate limited
+    #[serde(default)]
+    pub providers: Vec<TileProvider>,
+    /// raster MBTiles file, read before any provider
+    pub mbtiles: Option<String>,
+    /// downloaded tiles are stored on disk
+    pub cache: Option<TileCache>,
+}
+
+impl Osm {
+    /// Tile urls with their rate limit, in order of preference
+    pub fn get_providers(&self) -> impl Iterator<Item = (&str, Option<u32>)> {
+        self.tile_url
+            .iter()
+            .map(|url| (url.as_str(), None))
+            .chain(self.providers.iter().map(|p| (p.url.as_str(), p.rate)))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TileProvider {
+    pub url: String,
+    /// requests per second, exceeding ones go to the next provider
+    pub rate: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct TileCache {
+    pub path: String,
+    /// seconds before a tile is downloaded again
+    pub ttl: u64,
+    /// bytes kept on disk, the oldest tiles are removed first
+    pub max_size: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -61,30 +181,349 @@ pub struct Telegram {
     pub timeout: Option<u64>,
     // pub cache_chat: Option<String>,
     pub send_file: Option<bool>,
+    /// messages per second sent globally
+    pub global_rate: Option<u32>,
+    /// messages per second sent to a single chat
+    pub chat_rate: Option<u32>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    /// first day, as MM-DD
+    pub start: String,
+    /// last day, as MM-DD, can be before the first one to span the new year
+    pub end: String,
+    /// caption icons by message kind (pokemon, raid, lure, invasion, gym)
+    #[serde(default)]
+    pub icons: HashMap<String, String>,
+    /// folder inside sender replacing images/msg-bgs/, missing files fall back to the default ones
+    pub backgrounds: Option<String>,
+    /// text appended to every caption
+    pub footer: Option<String>,
+}
+
+impl Theme {
+    /// Parses a MM-DD date into the MMDD number
+    fn parse_day(s: &str) -> Option<u32> {
+        let (month, day) = s.split_once('-')?;
+        let (month, day): (u32, u32) = (month.parse().ok()?, day.parse().ok()?);
+        if (1..=12).contains(&month) && (1..=31).contains(&day) {
+            Some(month * 100 + day)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the theme is active on the given MMDD day
+    pub fn is_active(&self, day: u32) -> bool {
+        match (Self::parse_day(&self.start), Self::parse_day(&self.end)) {
+            (Some(start), Some(end)) if start <= end => (start..=end).contains(&day),
+            (Some(start), Some(end)) => day >= start || day <= end,
+            _ => false,
+        }
+    }
+}
+
+/// Christmas icons used before themes were configurable
+fn default_themes() -> Vec<Theme> {
+    vec![Theme {
+        name: String::from("natale"),
+        start: String::from("12-23"),
+        end: String::from("01-05"),
+        icons: [(String::from("pokemon"), String::from("🎁")), (String::from("raid"), String::from("🎄"))]
+            .into_iter()
+            .collect(),
+        backgrounds: None,
+        footer: None,
+    }]
 }
 
 impl Config {
-    fn new() -> Self {
-        let args: Vec<String> = env::args().collect();
+    fn default_path() -> Result<PathBuf, ()> {
+        if let Some(path) = env::var_os(PATH_VAR) {
+            return Ok(path.into());
+        }
+
+        #[cfg(test)]
+        let mut path = PathBuf::from("pokifications");
+        #[cfg(not(test))]
+        let mut path = env::current_exe().map_err(|e| error!("Cannot find executable path: {}", e))?;
+        // format!("{}.toml", path.file_stem().expect("Cannot find executable name").to_str().expect("Cannot parse executable name"))
+        if !path.set_extension("toml") {
+            error!("Cannot parse executable name: {}", path.display());
+            return Err(());
+        }
+        Ok(path)
+    }
+
+    fn read(path: Option<&Path>) -> Result<Table, ()> {
+        // an explicit path must exist, while the default one can be omitted when everything is set by env
+        let (config_file, required) = match path {
+            Some(p) => (p.to_path_buf(), true),
+            None => (Self::default_path()?, env::var_os(PATH_VAR).is_some()),
+        };
+
+        match fs::read_to_string(&config_file) {
+            Ok(s) => {
+                toml::from_str(&s).map_err(|e| error!("Syntax error on config file {}: {}", config_file.display(), e))
+            }
+            Err(e) if !required && e.kind() == ErrorKind::NotFound => {
+                info!("Config file {} not found, using environment variables only", config_file.display());
+                Ok(Table::new())
+            }
+            Err(e) => {
+                error!("Cannot read config file {}: {}", config_file.display(), e);
+                Err(())
+            }
+        }
+    }
+
+    /// Applies POKIFICATIONS_<SECTION>_<KEY> environment variables over the config file, nested keys are joined by a
+    /// double underscore (POKIFICATIONS_OSM_CACHE__TTL) and array items by their index (POKIFICATIONS_THEMES__0__FOOTER).
+    /// Returns the overridden paths with their raw values
+    fn apply_env(table: &mut Table, vars: impl Iterator<Item = (String, String)>) -> Vec<(Vec<String>, String)> {
+        let mut overrides = Vec::new();
+        for (name, raw) in vars {
+            let rest = match name.strip_prefix(ENV_PREFIX) {
+                Some(rest) if name != PATH_VAR && !rest.is_empty() => rest,
+                _ => continue,
+            };
+            let mut segments = rest.split("__");
+            let path: Vec<String> = match segments.next().and_then(|first| first.split_once('_')) {
+                Some((section, key)) => [section, key].into_iter().chain(segments).map(str::to_lowercase).collect(),
+                None => rest.split("__").map(str::to_lowercase).collect(),
+            };
+            if path.iter().any(String::is_empty) {
+                warn!("Environment variable {} ignored: invalid key", name);
+                continue;
+            }
+
+            let target = match Self::get_entry(table, &path) {
+                Some(target) => target,
+                None => {
+                    warn!("Environment variable {} ignored: {} isn't a config table", name, path.join("."));
+                    continue;
+                }
+            };
+            // keep strings as they are, otherwise numbers, booleans and arrays are parsed as toml values
+            *target = match target {
+                Value::String(_) => Value::String(raw.clone()),
+                _ => toml::from_str::<Table>(&format!("value = {}", raw))
+                    .ok()
+                    .and_then(|mut t| t.remove("value"))
+                    .unwrap_or_else(|| Value::String(raw.clone())),
+            };
+            debug!("Config key {} overridden by environment variable {}", path.join("."), name);
+            overrides.push((path, raw));
+        }
+        overrides
+    }
 
-        //config file can be the first argument
-        let config_file = if args.len() > 1 {
-            args.get(1).expect("Cannot retrieve config path").into()
+    /// Value at the given path, missing tables are created along the way
+    fn get_entry<'a>(table: &'a mut Table, path: &[String]) -> Option<&'a mut Value> {
+        let (first, rest) = path.split_first()?;
+        let mut current = table.entry(first.clone()).or_insert_with(|| Value::Table(Table::new()));
+        for key in rest {
+            current = match current {
+                Value::Table(t) => t.entry(key.clone()).or_insert_with(|| Value::Table(Table::new())),
+                Value::Array(a) => key.parse::<usize>().ok().and_then(|i| a.get_mut(i))?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Deserializes the config, env values parsed as toml but expected as strings (like numeric chat ids) are
+    /// retried as they were written
+    fn parse<T: DeserializeOwned>(mut table: Table, overrides: &[(Vec<String>, String)]) -> Result<T, String> {
+        loop {
+            let e = match serde_path_to_error::deserialize(Value::Table(table.clone())) {
+                Ok(config) => return Ok(config),
+                Err(e) => e,
+            };
+            let path = e
+                .path()
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Seq { index } => index.to_string(),
+                    Segment::Map { key } => key.clone(),
+                    Segment::Enum { variant } => variant.clone(),
+                    Segment::Unknown => String::new(),
+                })
+                .collect::<Vec<_>>();
+            match overrides.iter().find(|(p, _)| *p == path) {
+                Some((p, raw)) => match Self::get_entry(&mut table, p) {
+                    Some(target) if !matches!(target, Value::String(_)) => *target = Value::String(raw.clone()),
+                    _ => return Err(e.to_string()),
+                },
+                None => return Err(e.to_string()),
+            }
+        }
+    }
+
+    fn load() -> Result<Self, ()> {
+        let path = PATH.lock().map_err(|e| error!("Config lock error: {}", e))?.clone();
+        let mut table = Self::read(path.as_deref())?;
+        let overrides = Self::apply_env(&mut table, env::vars());
+        let config: Config = Self::parse(table, &overrides).map_err(|e| error!("Invalid config: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ()> {
+        let mut valid = true;
+        if self.service.queue_size == 0 {
+            error!("Invalid config: service.queue_size must be greater than 0");
+            valid = false;
+        }
+        for (url, rate) in self.osm.get_providers() {
+            if !["{z}", "{x}", "{y}"].iter().all(|p| url.contains(p)) {
+                error!("Invalid config: tile url {} must contain {{z}}, {{x}} and {{y}} placeholders", url);
+                valid = false;
+            }
+            if rate == Some(0) {
+                error!("Invalid config: tile provider {} rate must be greater than 0", url);
+                valid = false;
+            }
+        }
+        if self.osm.get_providers().next().is_none() && self.osm.mbtiles.is_none() {
+            error!("Invalid config: osm needs at least a tile provider or an MBTiles file");
+            valid = false;
+        }
+        if self.telegram.global_rate == Some(0) || self.telegram.chat_rate == Some(0) {
+            error!("Invalid config: telegram rates must be greater than 0");
+            valid = false;
+        }
+        if matches!(self.images.quality, Some(q) if q == 0 || q > 100) {
+            error!("Invalid config: images.quality must be between 1 and 100");
+            valid = false;
+        }
+        for theme in &self.themes {
+            if Theme::parse_day(&theme.start).is_none() || Theme::parse_day(&theme.end).is_none() {
+                error!("Invalid config: theme {} dates must be written as MM-DD", theme.name);
+                valid = false;
+            }
+        }
+        if valid {
+            Ok(())
         } else {
-            #[cfg(test)]
-            let mut path = PathBuf::from("pokifications");
-            #[cfg(not(test))]
-            let mut path = env::current_exe().expect("Cannot find executable path");
-            // format!("{}.toml", path.file_stem().expect("Cannot find executable name").to_str().expect("Cannot parse executable name"))
-            if !path.set_extension("toml") {
-                panic!("Canno parse executable name: {}", path.display());
+            Err(())
+        }
+    }
+
+    /// Lists settings which are read only at startup
+    fn needs_restart(&self, other: &Config) -> Vec<&'static str> {
+        let mut res = Vec::new();
+        if self.service.address != other.service.address {
+            res.push("service.address");
+        }
+        if self.service.port != other.service.port {
+            res.push("service.port");
+        }
+        if self.service.queue_size != other.service.queue_size {
+            res.push("service.queue_size");
+        }
+        if self.service.lru_size != other.service.lru_size {
+            res.push("service.lru_size");
+        }
+        if self.database.url != other.database.url {
+            res.push("database.url");
+        }
+        res
+    }
+}
+
+/// Loads the configuration, must be called before accessing CONFIG
+pub fn init(path: Option<PathBuf>) -> Result<(), ()> {
+    *PATH.lock().map_err(|e| error!("Config lock error: {}", e))? = path;
+    CONFIG.store(Config::load()?);
+    Ok(())
+}
+
+/// Reloads the configuration file, the running one is kept if the new one isn't valid
+pub async fn reload() -> Result<(), ()> {
+    // file reads and parsing are blocking
+    let config = tokio::task::spawn_blocking(Config::load)
+        .await
+        .map_err(|e| error!("Config reload task error: {}", e))?
+        .map_err(|_| error!("Config reload failed, keeping the running one"))?;
+    let fields = CONFIG.load().needs_restart(&config);
+    if !fields.is_empty() {
+        warn!("Config reloaded, but changes to {} will be applied only after a restart", fields.join(", "));
+    }
+    CONFIG.store(config);
+    info!("Config reloaded");
+    Ok(())
+}
+
+/// Reloads the configuration every time a SIGHUP is received
+#[cfg(unix)]
+pub async fn watch() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("SIGHUP handler error: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => {
+                reload().await.ok();
             }
-            path
-        };
-        let mut toml = File::open(&config_file).unwrap_or_else(|_| panic!("File {} not found", config_file.display()));
-        let mut s = String::new();
-        toml.read_to_string(&mut s).expect("Unable to read Toml file");
-        //read config file in toml format
-        toml::from_str(&s).expect("Syntax error on Tolm file")
+            _ = crate::shutdown::wait() => break,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn watch() {}
+
+#[cfg(test)]
+mod tests {
+    use toml::Table;
+
+    use super::{default_themes, Config};
+
+    #[test]
+    fn env_overrides() {
+        let mut table: Table = toml::from_str(include_str!("../pokifications.toml.example")).unwrap();
+        table.get_mut("telegram").and_then(|t| t.as_table_mut()).unwrap().remove("alert_chat");
+        let vars = [
+            ("POKIFICATIONS_TELEGRAM_ALERT_CHAT", "-100123"),
+            ("POKIFICATIONS_OSM_CACHE__PATH", "/tmp/tiles/"),
+            ("POKIFICATIONS_OSM_CACHE__TTL", "3600"),
+            ("POKIFICATIONS_THEMES__0__FOOTER", "Auguri"),
+            ("POKIFICATIONS_CONFIG", "ignored.toml"),
+        ];
+        let overrides = Config::apply_env(&mut table, vars.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        assert_eq!(overrides.len(), 4);
+
+        let config: Config = Config::parse(table, &overrides).unwrap();
+        assert_eq!(config.telegram.alert_chat.as_deref(), Some("-100123"));
+        assert_eq!(config.osm.cache.map(|c| c.ttl), Some(3600));
+        assert_eq!(config.themes[0].footer.as_deref(), Some("Auguri"));
+    }
+
+    #[test]
+    fn themes() {
+        let christmas = &default_themes()[0];
+        assert!(christmas.is_active(1225));
+        assert!(christmas.is_active(105));
+        assert!(!christmas.is_active(106));
+        assert!(!christmas.is_active(1222));
+
+        let mut summer = christmas.clone();
+        summer.start = String::from("07-01");
+        summer.end = String::from("08-31");
+        assert!(summer.is_active(815));
+        assert!(!summer.is_active(1225));
+
+        summer.end = String::from("13-01");
+        assert!(!summer.is_active(815));
     }
 }
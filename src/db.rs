@@ -1,7 +1,62 @@
-use mysql_async::Pool;
+use mysql_async::{params, prelude::Queryable, Pool};
 
 use once_cell::sync::Lazy;
 
+use tracing::{error, info};
+
 use crate::config::CONFIG;
 
-pub static MYSQL: Lazy<Pool> = Lazy::new(|| Pool::new(CONFIG.database.url.as_str()));
+pub static MYSQL: Lazy<Pool> = Lazy::new(|| Pool::new(CONFIG.load().database.url.as_str()));
+
+/// Embedded schema migrations, in order of version
+const MIGRATIONS: &[(u32, &str, &str)] = &[
+    (1, "initial", include_str!("../migrations/0001_initial.sql")),
+    (2, "raid_reminders", include_str!("../migrations/0002_raid_reminders.sql")),
+    (3, "sent_messages", include_str!("../migrations/0003_sent_messages.sql")),
+    (4, "weather_watch_reference", include_str!("../migrations/0004_weather_watch_reference.sql")),
+    (5, "pokemon_sizes", include_str!("../migrations/0005_pokemon_sizes.sql")),
+    (6, "grunt_lineups", include_str!("../migrations/0006_grunt_lineups.sql")),
+    (7, "bot_overrides", include_str!("../migrations/0007_bot_overrides.sql")),
+    (8, "sent_eggs", include_str!("../migrations/0008_sent_eggs.sql")),
+];
+
+/// Creates or upgrades the database schema, applying only the migrations not already recorded
+pub async fn migrate() -> Result<(), ()> {
+    let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    conn.query_drop(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INT UNSIGNED NOT NULL,
+            name VARCHAR(255) NOT NULL,
+            applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (version)
+        ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4",
+    )
+    .await
+    .map_err(|e| error!("MySQL query error: create schema_migrations\n{}", e))?;
+
+    let applied: Vec<u32> = conn
+        .query("SELECT version FROM schema_migrations")
+        .await
+        .map_err(|e| error!("MySQL query error: get applied migrations\n{}", e))?;
+
+    let mut count = 0;
+    for (version, name, sql) in MIGRATIONS.iter().filter(|(version, _, _)| !applied.contains(version)) {
+        info!("Applying migration {:04}_{}", version, name);
+        // sent as a single multi-statement query, so the server takes care of comments and string literals;
+        // MySQL commits DDL statements implicitly, so a failed migration has to be fixed by hand
+        conn.query_drop(*sql).await.map_err(|e| error!("MySQL migration {:04}_{} error: {}", version, name, e))?;
+        conn.exec_drop(
+            "INSERT INTO schema_migrations (version, name) VALUES (:version, :name)",
+            params! {
+                "version" => version,
+                "name" => name,
+            },
+        )
+        .await
+        .map_err(|e| error!("MySQL query error: insert migration\n{}", e))?;
+        count += 1;
+    }
+
+    info!("Database schema up to date, {} migrations applied", count);
+    Ok(())
+}
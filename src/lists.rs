@@ -16,20 +16,42 @@ use rocketmap_entities::gamemaster::Cache;
 use tokio::{
     spawn,
     sync::RwLock,
-    time::{interval_at, Duration, Instant},
+    time::{interval_at, sleep, Duration, Instant},
 };
 
 use once_cell::sync::Lazy;
 
-use tracing::error;
+use serde_json::Value;
 
+use tracing::{error, info};
+
+use crate::config::CONFIG;
 use crate::db::MYSQL;
 
+pub const GAMEMASTER_URL: &str = "https://raw.githubusercontent.com/PokeMiners/game_masters/master/latest/latest.json";
+pub const GRUNTS_URL: &str = "https://raw.githubusercontent.com/WatWowMap/event-info/main/grunts/formatted.json";
+
 pub static LIST: Lazy<ArcSwap<HashMap<u16, Pokemon>>> = Lazy::new(Default::default);
 // pub static SCANNED: Lazy<Vec<u16>> = Lazy::new(|| LIST.iter().filter(|(_, p)| p.scanned == 1).map(|(id, _)| *id).collect());
 // pub static COMMON: Lazy<Vec<u16>> = Lazy::new(|| LIST.iter().filter(|(_, p)| p.rarity < 2).map(|(id, _)| *id).collect());
 // pub static RARES: Lazy<Vec<u16>> = Lazy::new(|| LIST.iter().filter(|(_, p)| p.rarity >= 2).map(|(id, _)| *id).collect());
 
+/// Lists refresh period
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1800);
+/// First retry of a failed game master download, doubled at every further failure
+const GAMEMASTER_RETRY: Duration = Duration::from_secs(60);
+/// Game master download retries aren't spaced further than this
+const MAX_GAMEMASTER_RETRY: Duration = Duration::from_secs(21600);
+
+/// base height and weight of every species, from the game master
+static BASE_SIZES: Lazy<ArcSwap<HashMap<u16, (f64, f64)>>> = Lazy::new(Default::default);
+
+/// possible Pokémon for every battle slot and slots caught as reward
+type Lineup = (Vec<Vec<u16>>, Vec<u8>);
+
+/// current lineup of every grunt type
+static LINEUPS: Lazy<ArcSwap<HashMap<u16, Lineup>>> = Lazy::new(Default::default);
+
 pub static MOVES: Lazy<ArcSwap<HashMap<u16, String>>> = Lazy::new(Default::default);
 
 pub static FORMS: Lazy<ArcSwap<HashMap<u16, Form>>> = Lazy::new(Default::default);
@@ -47,18 +69,96 @@ pub struct Pokemon {
     pub id: u16,
     pub name: String,
     pub ptype: Option<String>,
+    /// base height in meters
+    pub height: Option<f64>,
+    /// base weight in kilograms
+    pub weight: Option<f64>,
     pub rarity: u8,
     pub scanned: u8,
     pub status: u8,
     pub raid: u8,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Size {
+    Xxs,
+    Xs,
+    Normal,
+    Xl,
+    Xxl,
+}
+
+impl Size {
+    /// Bit used by filters
+    pub fn get_bit(&self) -> u8 {
+        match self {
+            Size::Xxs => 1,
+            Size::Xs => 2,
+            Size::Normal => 0,
+            Size::Xl => 4,
+            Size::Xxl => 8,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Size::Xxs => "XXS",
+            Size::Xs => "XS",
+            Size::Normal => "",
+            Size::Xl => "XL",
+            Size::Xxl => "XXL",
+        }
+    }
+}
+
+/// Last Pokédex number of every generation
+const GENERATIONS: [u16; 9] = [151, 251, 386, 493, 649, 721, 809, 905, 1025];
+
+impl Pokemon {
+    /// Type names, type is stored like "Grass/Poison"
+    pub fn get_types(&self) -> impl Iterator<Item = &str> {
+        self.ptype.as_deref().unwrap_or_default().split(|c: char| !c.is_alphabetic()).filter(|t| !t.is_empty())
+    }
+
+    pub fn has_type(&self, ptype: &str) -> bool {
+        self.get_types().any(|t| t.eq_ignore_ascii_case(ptype))
+    }
+
+    /// Size class of a spawn, from the mean of its height and weight ratios to the base ones, the same sum used by the
+    /// old XS and XL medals, or from the height alone when the weight is unknown
+    pub fn get_size(&self, height: Option<f64>, weight: Option<f64>) -> Option<Size> {
+        let height_ratio = height? / self.height.filter(|h| *h > 0_f64)?;
+        let ratio = match (weight, self.weight.filter(|w| *w > 0_f64)) {
+            (Some(weight), Some(base)) => (height_ratio + weight / base) / 2_f64,
+            _ => height_ratio,
+        };
+        Some(if ratio <= 0.5 {
+            Size::Xxs
+        } else if ratio < 0.75 {
+            Size::Xs
+        } else if ratio >= 1.5 {
+            Size::Xxl
+        } else if ratio > 1.25 {
+            Size::Xl
+        } else {
+            Size::Normal
+        })
+    }
+
+    pub fn get_generation(&self) -> Option<u8> {
+        GENERATIONS.iter().position(|last| self.id <= *last).map(|i| i as u8 + 1)
+    }
+}
+
 impl FromRow for Pokemon {
     fn from_row_opt(mut row: Row) -> Result<Self, mysql_async::FromRowError> {
         Ok(Pokemon {
             id: row.take("id").expect("MySQL pokemon_list.id error"),
             name: row.take("name").expect("MySQL pokemon_list.name error"),
             ptype: row.take("type").expect("MySQL pokemon_list.type error"),
+            // filled from the game master and bot_pokemon_sizes
+            height: None,
+            weight: None,
             rarity: row.take("rarity").expect("MySQL pokemon_list.rarity error"),
             scanned: row.take("scanned").expect("MySQL pokemon_list.scanned error"),
             status: row.take("status").expect("MySQL pokemon_list.status error"),
@@ -118,11 +218,34 @@ impl Cache for FormCache {
     }
 }
 
+/// Rocket leaders Cliff, Arlo and Sierra
+pub const LEADERS: [u16; 3] = [41, 42, 43];
+pub const GIOVANNI: u16 = 44;
+
 pub struct GruntType {
     pub id: u16,
     pub name: String,
     pub sex: Option<String>,
     pub element: Option<String>,
+    /// possible Pokémon for every battle slot
+    pub lineup: Vec<Vec<u16>>,
+    /// battle slots, starting from 1, whose Pokémon can be caught as reward
+    pub rewards: Vec<u8>,
+}
+
+impl GruntType {
+    pub fn is_leader(&self) -> bool {
+        LEADERS.contains(&self.id)
+    }
+
+    pub fn is_giovanni(&self) -> bool {
+        self.id == GIOVANNI
+    }
+
+    /// Pokémon which can be caught after the battle
+    pub fn get_rewards(&self) -> impl Iterator<Item = u16> + '_ {
+        self.rewards.iter().filter_map(|slot| self.lineup.get(usize::from(*slot).checked_sub(1)?)).flatten().copied()
+    }
 }
 
 impl FromRow for GruntType {
@@ -132,6 +255,9 @@ impl FromRow for GruntType {
             name: row.take("name").expect("MySQL grunt_types.name error"),
             sex: row.take("sex").expect("MySQL grunt_types.type error"),
             element: row.take("type").expect("MySQL grunt_types.rarity error"),
+            // filled from the downloaded lineups and bot_grunt_lineups
+            lineup: Vec::new(),
+            rewards: Vec::new(),
         })
     }
 }
@@ -241,8 +367,121 @@ impl FromRow for CityPark {
     }
 }
 
+/// Json from a local file or an url
+async fn fetch_json(source: &str) -> Result<Value, ()> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let res = reqwest::get(source).await.map_err(|e| error!("error retrieving {}: {}", source, e))?;
+        if !res.status().is_success() {
+            error!("{} retriever failed with status code {}", source, res.status());
+            return Err(());
+        }
+        res.json().await.map_err(|e| error!("error decoding {}: {}", source, e))
+    } else {
+        let bytes = tokio::fs::read(source).await.map_err(|e| error!("error reading {}: {}", source, e))?;
+        serde_json::from_slice(&bytes).map_err(|e| error!("error decoding {}: {}", source, e))
+    }
+}
+
+/// Base height and weight from the species templates of the game master (e.g. V0001_POKEMON_BULBASAUR), skipping the
+/// form specific ones
+fn parse_base_sizes(gamemaster: &Value) -> HashMap<u16, (f64, f64)> {
+    gamemaster
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let template = item.get("templateId")?.as_str()?;
+            let settings = item.get("data")?.get("pokemonSettings")?;
+            let id: u16 = template.get(1..5)?.parse().ok()?;
+            if template != format!("V{:04}_POKEMON_{}", id, settings.get("pokemonId")?.as_str()?) {
+                return None;
+            }
+            Some((id, (settings.get("pokedexHeightM")?.as_f64()?, settings.get("pokedexWeightKg")?.as_f64()?)))
+        })
+        .collect()
+}
+
+async fn load_base_sizes() -> Result<(), ()> {
+    let source = CONFIG.load().lists.gamemaster.clone().unwrap_or_else(|| GAMEMASTER_URL.to_owned());
+    let sizes = parse_base_sizes(&fetch_json(&source).await?);
+    if sizes.is_empty() {
+        error!("no Pokémon size found in game master {}", source);
+        return Err(());
+    }
+    info!("Loaded base sizes of {} Pokémon", sizes.len());
+    BASE_SIZES.swap(Arc::new(sizes));
+    Ok(())
+}
+
+/// Lineups from the event-info grunts format, where reward slots start from 0
+fn parse_lineups(grunts: &Value) -> HashMap<u16, Lineup> {
+    grunts
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(id, grunt)| {
+            let lineup = grunt.get("lineup")?;
+            let team = lineup
+                .get("team")?
+                .as_array()?
+                .iter()
+                .map(|slot| {
+                    slot.as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|p| p.get("id")?.as_u64()?.try_into().ok())
+                        .collect::<Vec<u16>>()
+                })
+                .collect();
+            let rewards = lineup
+                .get("rewards")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|slot| u8::try_from(slot.as_u64()?).ok()?.checked_add(1))
+                .collect();
+            Some((id.parse().ok()?, (team, rewards)))
+        })
+        .collect()
+}
+
+/// Lineup written as "1,4;2,5;3,6" and reward slots, starting from 1, as "1,2"
+fn parse_override(lineup: &str, rewards: &str) -> Lineup {
+    (
+        lineup
+            .split(';')
+            .map(|slot| slot.split(',').filter_map(|id| id.trim().parse().ok()).collect::<Vec<_>>())
+            .filter(|slot| !slot.is_empty())
+            .collect(),
+        rewards.split(',').filter_map(|s| s.trim().parse().ok()).collect(),
+    )
+}
+
+async fn load_lineups() -> Result<(), ()> {
+    let source = CONFIG.load().lists.grunts.clone().unwrap_or_else(|| GRUNTS_URL.to_owned());
+    let lineups = parse_lineups(&fetch_json(&source).await?);
+    if lineups.is_empty() {
+        error!("no grunt lineup found in {}", source);
+        return Err(());
+    }
+    LINEUPS.swap(Arc::new(lineups));
+    Ok(())
+}
+
 async fn load_pokemons() -> Result<(), ()> {
+    let sizes = BASE_SIZES.load();
+
     let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    // the table is missing until migration 7, sizes from the game master are enough
+    let overrides: HashMap<u16, (Option<f64>, Option<f64>)> = conn
+        .query_map("SELECT pokemon_id, height, weight FROM bot_pokemon_sizes", |(id, height, weight)| {
+            (id, (height, weight))
+        })
+        .await
+        .map_err(|e| error!("MySQL query error: get pokemon sizes\n{}", e))
+        .map(|rows| rows.into_iter().collect())
+        .unwrap_or_default();
+
     let res = conn
         .query_iter("SELECT * FROM pokemon_list")
         .await
@@ -253,7 +492,13 @@ async fn load_pokemons() -> Result<(), ()> {
         .await
         .map_err(|e| error!("MySQL load_pokemons error: {}", e))?
         .ok_or_else(|| error!("MySQL load_pokemons empty"))?
-        .map_ok(|p| (p.id, p))
+        .map_ok(|mut p| {
+            let (height, weight) = overrides.get(&p.id).copied().unwrap_or_default();
+            let base = sizes.get(&p.id);
+            p.height = height.or(base.map(|(h, _)| *h));
+            p.weight = weight.or(base.map(|(_, w)| *w));
+            (p.id, p)
+        })
         .try_collect()
         .await
         .map_err(|e| error!("MySQL load_pokemons collect error: {}", e))?;
@@ -304,7 +549,21 @@ async fn load_forms() -> Result<(), ()> {
 }
 
 async fn load_grunts() -> Result<(), ()> {
+    // lineups rotate with events, on errors the previous ones are kept
+    load_lineups().await.ok();
+    let lineups = LINEUPS.load();
     let mut conn = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e))?;
+    // the table is missing until migration 7, downloaded lineups are enough
+    let overrides: HashMap<u16, Lineup> = conn
+        .query_map(
+            "SELECT grunt_type, lineup, rewards FROM bot_grunt_lineups",
+            |(id, lineup, rewards): (u16, String, String)| (id, parse_override(&lineup, &rewards)),
+        )
+        .await
+        .map_err(|e| error!("MySQL query error: get grunt lineups\n{}", e))
+        .map(|rows| rows.into_iter().collect())
+        .unwrap_or_default();
+
     let res = conn
         .query_iter("SELECT * FROM grunt_types")
         .await
@@ -315,7 +574,13 @@ async fn load_grunts() -> Result<(), ()> {
         .await
         .map_err(|e| error!("MySQL load_grunts error: {}", e))?
         .ok_or_else(|| error!("MySQL load_grunts empty"))?
-        .map_ok(|gt| (gt.id, gt))
+        .map_ok(|mut gt| {
+            if let Some((lineup, rewards)) = overrides.get(&gt.id).or_else(|| lineups.get(&gt.id)) {
+                gt.lineup = lineup.clone();
+                gt.rewards = rewards.clone();
+            }
+            (gt.id, gt)
+        })
         .try_collect()
         .await
         .map_err(|e| error!("MySQL load_grunts collect error: {}", e))?;
@@ -384,15 +649,66 @@ async fn load() {
     .await;
 }
 
+/// Retries the game master download with exponential backoff, then applies the sizes to the Pokémon list
+async fn retry_base_sizes() {
+    let mut delay = GAMEMASTER_RETRY;
+    loop {
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = crate::shutdown::wait() => return,
+        }
+        if load_base_sizes().await.is_ok() {
+            load_pokemons().await.ok();
+            return;
+        }
+        delay = (delay * 2).min(MAX_GAMEMASTER_RETRY);
+    }
+}
+
 pub async fn init() {
+    // the game master rarely changes, it's downloaded only until the first success
+    let sizes = load_base_sizes().await;
     // force first load
     load().await;
+    if sizes.is_err() {
+        spawn(retry_base_sizes());
+    }
     spawn(async {
-        let period = Duration::from_secs(1800);
-        let mut interval = interval_at(Instant::now() + period, period);
+        let mut interval = interval_at(Instant::now() + RELOAD_INTERVAL, RELOAD_INTERVAL);
         loop {
-            interval.tick().await;
-            load().await;
+            tokio::select! {
+                _ = interval.tick() => load().await,
+                _ = crate::shutdown::wait() => break,
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_base_sizes, parse_lineups, parse_override};
+
+    #[test]
+    fn base_sizes() {
+        let gamemaster = serde_json::json!([
+            {"templateId": "V0019_POKEMON_RATTATA", "data": {"pokemonSettings": {"pokemonId": "RATTATA", "pokedexHeightM": 0.3, "pokedexWeightKg": 3.5}}},
+            {"templateId": "V0019_POKEMON_RATTATA_ALOLA", "data": {"pokemonSettings": {"pokemonId": "RATTATA", "form": "RATTATA_ALOLA", "pokedexHeightM": 0.3, "pokedexWeightKg": 3.8}}},
+            {"templateId": "COMBAT_LEAGUE_DEFAULT_GREAT", "data": {}}
+        ]);
+        let sizes = parse_base_sizes(&gamemaster);
+        assert_eq!(sizes.len(), 1);
+        assert_eq!(sizes.get(&19), Some(&(0.3, 3.5)));
+    }
+
+    #[test]
+    fn lineups() {
+        let grunts = serde_json::json!({
+            "4": {"active": true, "lineup": {"rewards": [0], "team": [[{"id": 129, "form": 0}], [{"id": 129}, {"id": 55}], [{"id": 130}]]}},
+            "5": {"active": true}
+        });
+        let lineups = parse_lineups(&grunts);
+        assert_eq!(lineups.len(), 1);
+        assert_eq!(lineups.get(&4), Some(&(vec![vec![129], vec![129, 55], vec![130]], vec![1])));
+        assert_eq!(lineups.get(&4), Some(&parse_override("129;129, 55;130", "1")));
+    }
+}
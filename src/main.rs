@@ -10,19 +10,23 @@
 mod alerts;
 mod bot;
 mod cleanup;
+mod cli;
 mod config;
 mod db;
 mod lists;
+mod shutdown;
 mod telegram;
 
+use std::path::PathBuf;
+
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{Body, Request, Response, Server};
+use hyper::{Body, Request, Response, Server, StatusCode};
 
 use futures_util::TryStreamExt;
 
 use mysql_async::prelude::Queryable;
 
-use tokio::spawn;
+use tokio::{spawn, time::Duration};
 // use tokio::fs::File;
 // use tokio::prelude::*;
 
@@ -32,7 +36,11 @@ use serde_json::value::Value;
 
 use tracing::{debug, error, info};
 
+use crate::cli::Command;
 use crate::db::MYSQL;
+use crate::shutdown::InFlight;
+
+const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 30;
 
 // async fn log_webhook(bytes: &[u8]) -> Result<(), ()> {
 //     let filename = format!("{}log/{}.log", config::CONFIG.images.bot, Utc::now().with_timezone(&Rome).format("%Y%m%d%H%M%S%f").to_string());
@@ -53,7 +61,9 @@ async fn parse(now: DateTime<Utc>, bytes: Vec<u8>, platform: Platform) -> Result
     let configs: Vec<Value> = serde_json::from_str(&body).map_err(|e| error!("deserialize error: {}\n{}", e, body))?;
 
     let count = configs.len();
+    let guard = InFlight::start();
     spawn(async move {
+        let _guard = guard;
         if let Ok(mut conn) = MYSQL.get_conn().await.map_err(|e| error!("MySQL retrieve connection error: {}", e)) {
             conn.query_drop(format!("INSERT INTO bot_stats (day, events) VALUES (CURDATE(), {0}) ON DUPLICATE KEY UPDATE events = events + {0}", count)).await
                 .map_err(|e| error!("MySQL update bot stats error: {}", e))
@@ -100,18 +110,19 @@ impl std::fmt::Display for Platform {
 }
 
 fn check_safeword(req: &Request<Body>) -> Option<Platform> {
-    if config::CONFIG.service.safeword.is_none()
-        && config::CONFIG.service.rdm_safeword.is_none()
-        && config::CONFIG.service.rdm_safeword.is_none()
+    let config = config::CONFIG.load();
+    if config.service.safeword.is_none()
+        && config.service.rdm_safeword.is_none()
+        && config.service.rdm_safeword.is_none()
     {
         None
     } else {
         let path = Some(req.uri().path().trim_matches('/'));
-        if path == config::CONFIG.service.safeword.as_deref() {
+        if path == config.service.safeword.as_deref() {
             Some(Platform::Unknown)
-        } else if path == config::CONFIG.service.rdm_safeword.as_deref() {
+        } else if path == config.service.rdm_safeword.as_deref() {
             Some(Platform::Rdm)
-        } else if path == config::CONFIG.service.mad_safeword.as_deref() {
+        } else if path == config.service.mad_safeword.as_deref() {
             Some(Platform::Mad)
         } else {
             None
@@ -119,7 +130,30 @@ fn check_safeword(req: &Request<Body>) -> Option<Platform> {
     }
 }
 
+fn check_admin(req: &Request<Body>, command: &str) -> bool {
+    let config = config::CONFIG.load();
+    match config.service.admin_safeword.as_deref() {
+        Some(admin) if !admin.is_empty() => req.uri().path().trim_matches('/') == format!("{}/{}", admin, command),
+        _ => false,
+    }
+}
+
 async fn service(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+    // connections kept alive could still deliver webhooks while draining, let the sender retry them later
+    if shutdown::is_shutting_down() {
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        return Ok(res);
+    }
+
+    if check_admin(&req, "reload") {
+        let mut res = Response::new(Body::empty());
+        if config::reload().await.is_err() {
+            *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        }
+        return Ok(res);
+    }
+
     if let Some(platform) = check_safeword(&req) {
         let now = Utc::now();
         let bytes = req.into_body().map_ok(|c| c.to_vec()).try_concat().await.map_err(|e| {
@@ -128,8 +162,10 @@ async fn service(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
         })?;
 
         //spawn an independent future to parse the stream
+        let guard = InFlight::start();
         spawn(async move {
             parse(now, bytes, platform).await.ok();
+            drop(guard);
         });
     }
 
@@ -137,23 +173,20 @@ async fn service(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
     Ok(Response::new(Body::empty()))
 }
 
-/// Launch service according to config
-#[tokio::main]
-async fn main() -> Result<(), ()> {
-    tracing_subscriber::fmt::init();
+async fn serve() -> Result<(), ()> {
+    spawn(shutdown::signal());
+    spawn(config::watch());
 
     rocketmap_entities::gamemaster::init();
 
     cleanup::init();
 
     //retrieve address and port, defaulting if not configured
-    let addr = format!(
-        "{}:{}",
-        config::CONFIG.service.address.as_deref().unwrap_or("0.0.0.0"),
-        config::CONFIG.service.port.unwrap_or(80)
-    )
-    .parse()
-    .map_err(|e| error!("Error parsing webserver address: {}", e))?;
+    let config = config::CONFIG.load_full();
+    let addr =
+        format!("{}:{}", config.service.address.as_deref().unwrap_or("0.0.0.0"), config.service.port.unwrap_or(80))
+            .parse()
+            .map_err(|e| error!("Error parsing webserver address: {}", e))?;
 
     //basic service function
     let service = make_service_fn(|_| async { Ok::<_, hyper::Error>(service_fn(service)) });
@@ -167,12 +200,64 @@ async fn main() -> Result<(), ()> {
         // bind and serve...
         Server::bind(&addr)
             .serve(service)
+            .with_graceful_shutdown(shutdown::wait())
             .await
             .map_err(|e| {
                 error!("server error: {}", e);
             })
             .ok();
+
+        info!("Webserver stopped, draining pending notifications");
+        drain().await;
     }
 
     Ok(())
 }
+
+async fn drain() {
+    let timeout = config::CONFIG.load().service.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT);
+    shutdown::drain(Duration::from_secs(timeout), bot::BotConfigs::pending).await.ok();
+}
+
+async fn replay(file: PathBuf, platform: Platform) -> Result<(), ()> {
+    let bytes = tokio::fs::read(&file).await.map_err(|e| error!("Cannot read payload {}: {}", file.display(), e))?;
+
+    rocketmap_entities::gamemaster::init();
+    lists::init().await;
+    bot::BotConfigs::init().await?;
+
+    parse(Utc::now(), bytes, platform).await?;
+    drain().await;
+
+    Ok(())
+}
+
+/// Launch service according to config
+#[tokio::main]
+async fn main() -> Result<(), ()> {
+    tracing_subscriber::fmt::init();
+
+    let args = cli::Args::parse().map_err(|e| eprintln!("{}\n\n{}", e, cli::USAGE))?;
+    if let Command::Help = args.command {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    config::init(args.config).map_err(|_| eprintln!("Invalid configuration, see errors above"))?;
+
+    match args.command {
+        Command::Serve => serve().await,
+        Command::CheckConfig => {
+            println!("Configuration is valid");
+            Ok(())
+        }
+        Command::Replay { file, platform } => replay(file, platform).await,
+        Command::RenderSample { kind, input, output } => {
+            rocketmap_entities::gamemaster::init();
+            lists::init().await;
+            bot::render_sample(&kind, &input, &output).await
+        }
+        Command::Migrate => db::migrate().await,
+        Command::Help => Ok(()),
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use once_cell::sync::Lazy;
+
+use tokio::{
+    sync::watch,
+    time::{sleep, Duration, Instant},
+};
+
+use tracing::{error, info, warn};
+
+static SHUTDOWN: Lazy<watch::Sender<bool>> = Lazy::new(|| watch::channel(false).0);
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Marks a unit of work that must be completed before exiting, released on drop
+pub struct InFlight(());
+
+impl InFlight {
+    pub fn start() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        InFlight(())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn is_shutting_down() -> bool {
+    *SHUTDOWN.borrow()
+}
+
+/// Resolves once shutdown has been requested
+pub async fn wait() {
+    // the sender lives in a static, so the channel can't be closed
+    SHUTDOWN.subscribe().wait_for(|stop| *stop).await.ok();
+}
+
+/// Waits for SIGINT or SIGTERM, then requests shutdown
+pub async fn signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(e) => {
+                error!("SIGTERM handler error: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => {
+            if let Err(e) = res {
+                error!("SIGINT handler error: {}", e);
+                return;
+            }
+        }
+        _ = terminate => {}
+    }
+
+    info!("Shutdown requested");
+    SHUTDOWN.send_replace(true);
+}
+
+/// Waits for in-flight work and queued items to complete, up to the given timeout
+pub async fn drain<F>(timeout: Duration, queued: F) -> Result<(), ()>
+where
+    F: Fn() -> usize,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        let pending = IN_FLIGHT.load(Ordering::SeqCst) + queued();
+        if pending == 0 {
+            info!("All pending notifications drained");
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            warn!("Shutdown timeout reached with {} pending notifications", pending);
+            return Err(());
+        }
+
+        sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
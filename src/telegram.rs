@@ -65,7 +65,7 @@ pub enum Image {
 pub async fn call_telegram(/*chat_id: String, */ req: RequestBuilder) -> Result<String, CallResult> {
     // wall(chat_id).await;
 
-    let res = if let Some(t) = CONFIG.telegram.timeout { req.timeout(Duration::from_secs(t)) } else { req }
+    let res = if let Some(t) = CONFIG.load().telegram.timeout { req.timeout(Duration::from_secs(t)) } else { req }
         .send()
         .await
         .map_err(|e| {
@@ -237,12 +237,16 @@ impl<'a> SendPhoto<'a> {
                 form = form.text("photo", url);
             }
             Image::Bytes(bytes) => {
+                let format = CONFIG.load().images.format;
                 form = form.part(
                     "photo",
-                    Part::stream(Body::from(bytes)).file_name("image.png").mime_str("image/png").map_err(|e| {
-                        error!("error writing multipart mime: {}", e);
-                        CallResult::Empty
-                    })?,
+                    Part::stream(Body::from(bytes))
+                        .file_name(format!("image.{}", format.get_extension()))
+                        .mime_str(format.get_mime())
+                        .map_err(|e| {
+                            error!("error writing multipart mime: {}", e);
+                            CallResult::Empty
+                        })?,
                 );
             }
         }
@@ -269,6 +273,192 @@ pub fn send_photo<'a>(bot_token: &'a str, chat_id: &'a str, photo: Image) -> Sen
     }
 }
 
+pub struct EditMessageMedia<'a> {
+    bot_token: &'a str,
+    chat_id: &'a str,
+    message_id: i64,
+    photo: Image,
+    caption: Option<&'a str>,
+    parse_mode: Option<&'a str>,
+    reply_markup: Option<Value>,
+}
+
+impl<'a> EditMessageMedia<'a> {
+    pub fn set_caption(mut self, caption: &'a str) -> Self {
+        self.caption = Some(caption);
+        self
+    }
+    // pub fn set_parse_mode(mut self, parse_mode: &'a str) -> Self {
+    //     self.parse_mode = Some(parse_mode);
+    //     self
+    // }
+    pub fn set_reply_markup(mut self, reply_markup: Value) -> Self {
+        self.reply_markup = Some(reply_markup);
+        self
+    }
+    fn get_media(&self) -> Value {
+        let mut media = json!({
+            "type": "photo",
+            "media": match &self.photo {
+                Image::FileUrl(url) => url.as_str(),
+                // uploaded files are referenced by the name of their multipart field
+                Image::Bytes(_) => "attach://photo",
+            },
+        });
+        if let Some(v) = self.caption {
+            media["caption"] = Value::from(v);
+        }
+        if let Some(v) = self.parse_mode {
+            media["parse_mode"] = Value::from(v);
+        }
+        media
+    }
+    pub async fn send(self) -> Result<String, CallResult> {
+        let url =
+            Url::parse(&format!("https://api.telegram.org/bot{}/editMessageMedia", self.bot_token)).map_err(|e| {
+                error!("error building Telegram URL: {}", e);
+                CallResult::Empty
+            })?;
+        let boundary: String = thread_rng().sample_iter(&Alphanumeric).take(30).map(char::from).collect();
+
+        let mut form = Form::new()
+            .text("chat_id", self.chat_id.to_owned())
+            .text("message_id", self.message_id.to_string())
+            .text("media", self.get_media().to_string());
+
+        if let Some(v) = self.reply_markup {
+            form = form.text("reply_markup", v.to_string());
+        }
+
+        if let Image::Bytes(bytes) = self.photo {
+            let format = CONFIG.load().images.format;
+            form = form.part(
+                "photo",
+                Part::stream(Body::from(bytes))
+                    .file_name(format!("image.{}", format.get_extension()))
+                    .mime_str(format.get_mime())
+                    .map_err(|e| {
+                        error!("error writing multipart mime: {}", e);
+                        CallResult::Empty
+                    })?,
+            );
+        }
+
+        let client = Client::new();
+        let req = client
+            .request(Method::POST, url)
+            .header("Content-Type", &format!("multipart/form-data; boundary={}", boundary))
+            .multipart(form);
+        call_telegram(req).await
+    }
+}
+
+pub fn edit_message_media<'a>(
+    bot_token: &'a str,
+    chat_id: &'a str,
+    message_id: i64,
+    photo: Image,
+) -> EditMessageMedia<'a> {
+    EditMessageMedia { bot_token, chat_id, message_id, photo, caption: None, parse_mode: None, reply_markup: None }
+}
+
+pub struct EditMessageCaption<'a> {
+    bot_token: &'a str,
+    chat_id: &'a str,
+    message_id: i64,
+    caption: &'a str,
+    parse_mode: Option<&'a str>,
+    reply_markup: Option<Value>,
+}
+
+impl<'a> EditMessageCaption<'a> {
+    // pub fn set_parse_mode(mut self, parse_mode: &'a str) -> Self {
+    //     self.parse_mode = Some(parse_mode);
+    //     self
+    // }
+    pub fn set_reply_markup(mut self, reply_markup: Value) -> Self {
+        self.reply_markup = Some(reply_markup);
+        self
+    }
+    fn get_body(&self) -> Value {
+        let mut body = json!({
+            "chat_id": self.chat_id,
+            "message_id": self.message_id,
+            "caption": self.caption
+        });
+
+        if let Some(v) = self.parse_mode {
+            body["parse_mode"] = Value::from(v);
+        }
+
+        if let Some(v) = &self.reply_markup {
+            body["reply_markup"] = v.clone();
+        }
+
+        body
+    }
+    pub async fn send(self) -> Result<String, CallResult> {
+        let url =
+            Url::parse(&format!("https://api.telegram.org/bot{}/editMessageCaption", self.bot_token)).map_err(|e| {
+                error!("error building Telegram URL: {}", e);
+                CallResult::Empty
+            })?;
+        let body = self.get_body();
+
+        let client = Client::new();
+        let req = client.request(Method::POST, url).header("Content-Type", "application/json").json(&body);
+        call_telegram(req).await
+    }
+}
+
+pub fn edit_message_caption<'a>(
+    bot_token: &'a str,
+    chat_id: &'a str,
+    message_id: i64,
+    caption: &'a str,
+) -> EditMessageCaption<'a> {
+    EditMessageCaption { bot_token, chat_id, message_id, caption, parse_mode: None, reply_markup: None }
+}
+
+pub struct DeleteMessage<'a> {
+    bot_token: &'a str,
+    chat_id: &'a str,
+    message_id: i64,
+}
+
+impl<'a> DeleteMessage<'a> {
+    fn get_body(&self) -> Value {
+        json!({
+            "chat_id": self.chat_id,
+            "message_id": self.message_id
+        })
+    }
+    pub async fn send(self) -> Result<String, CallResult> {
+        let url =
+            Url::parse(&format!("https://api.telegram.org/bot{}/deleteMessage", self.bot_token)).map_err(|e| {
+                error!("error building Telegram URL: {}", e);
+                CallResult::Empty
+            })?;
+        let body = self.get_body();
+
+        let client = Client::new();
+        let req = client.request(Method::POST, url).header("Content-Type", "application/json").json(&body);
+        call_telegram(req).await
+    }
+}
+
+pub fn delete_message<'a>(bot_token: &'a str, chat_id: &'a str, message_id: i64) -> DeleteMessage<'a> {
+    DeleteMessage { bot_token, chat_id, message_id }
+}
+
+/// Extracts the id of the message from a Telegram response
+pub fn get_message_id(body: &str) -> Option<i64> {
+    serde_json::from_str::<Value>(body)
+        .map_err(|e| error!("error while decoding {}: {}", body, e))
+        .ok()
+        .and_then(|json| json["result"]["message_id"].as_i64())
+}
+
 // pub fn init() {
 //     spawn(async {
 //         // start next leap second
@@ -283,3 +473,44 @@ pub fn send_photo<'a>(bot_token: &'a str, chat_id: &'a str, photo: Image) -> Sen
 //         }
 //     });
 // }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{delete_message, edit_message_caption, edit_message_media, get_message_id, Image};
+
+    #[test]
+    fn edit_media() {
+        let remote = edit_message_media("token", "123", 45, Image::FileUrl(String::from("https://example.com/a.png")))
+            .set_caption("caption");
+        assert_eq!(
+            remote.get_media(),
+            json!({"type": "photo", "media": "https://example.com/a.png", "caption": "caption"})
+        );
+
+        let bytes = edit_message_media("token", "123", 45, Image::Bytes(vec![0, 1, 2]));
+        assert_eq!(bytes.get_media(), json!({"type": "photo", "media": "attach://photo"}));
+    }
+
+    #[test]
+    fn edit_caption() {
+        let markup = json!({"inline_keyboard": []});
+        let edit = edit_message_caption("token", "123", 45, "caption").set_reply_markup(markup.clone());
+        assert_eq!(
+            edit.get_body(),
+            json!({"chat_id": "123", "message_id": 45, "caption": "caption", "reply_markup": markup})
+        );
+    }
+
+    #[test]
+    fn delete() {
+        assert_eq!(delete_message("token", "123", 45).get_body(), json!({"chat_id": "123", "message_id": 45}));
+    }
+
+    #[test]
+    fn message_id() {
+        assert_eq!(get_message_id(r#"{"ok":true,"result":{"message_id":45}}"#), Some(45));
+        assert_eq!(get_message_id(r#"{"ok":true,"result":true}"#), None);
+    }
+}